@@ -0,0 +1,139 @@
+use super::parse::Syntax;
+use bitcoin::blockdata::opcodes::{all::*, All as Opcode};
+use proc_macro2::Span;
+
+/// Checks which are always run over the parsed script, returning errors for
+/// patterns which can never be what was intended.
+pub fn check(syntax: &[(Syntax, Span)]) -> Vec<(Span, String)> {
+    let mut errors = Vec::new();
+
+    for (i, (item, _)) in syntax.iter().enumerate() {
+        if let Syntax::Opcode(opcode @ (OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY)) = item {
+            errors.extend(check_multisig(*opcode, &syntax[..i]));
+        }
+    }
+
+    errors
+}
+
+// in the bare multisig pattern `m <key>... n OP_CHECKMULTISIG`, `n` has to
+// match the number of keys. anything which doesn't look like this pattern
+// (e.g. keys or counts pushed from escapes) is left alone.
+fn check_multisig(opcode: Opcode, preceding: &[(Syntax, Span)]) -> Option<(Span, String)> {
+    let ((count, span), rest) = preceding.split_last()?;
+    let count = small_int(count)?;
+
+    let keys = rest
+        .iter()
+        .rev()
+        .take_while(|(item, _)| matches!(item, Syntax::Bytes(bytes) if is_key(bytes)))
+        .count();
+    let (threshold, _) = rest.get(rest.len().checked_sub(keys + 1)?)?;
+    small_int(threshold)?;
+
+    if keys as i64 == count {
+        return None;
+    }
+
+    Some((
+        *span,
+        format!(
+            "{:?} expects {} keys, but {} are pushed",
+            opcode, count, keys
+        ),
+    ))
+}
+
+// accepts both the `OP_PUSHNUM_N` and integer literal spellings
+fn small_int(item: &Syntax) -> Option<i64> {
+    match item {
+        Syntax::Int(n @ 0..=16) => Some(*n),
+        Syntax::Opcode(opcode) => {
+            let code = opcode.to_u8();
+            if code == OP_PUSHBYTES_0.to_u8() {
+                Some(0)
+            } else if (OP_PUSHNUM_1.to_u8()..=OP_PUSHNUM_16.to_u8()).contains(&code) {
+                Some((code - OP_PUSHNUM_1.to_u8() + 1) as i64)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+// compressed or uncompressed public key
+fn is_key(bytes: &[u8]) -> bool {
+    bytes.len() == 33 || bytes.len() == 65
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse;
+    use quote::quote;
+
+    #[test]
+    fn check_multisig_ok() {
+        let syntax = parse(quote!(
+            OP_PUSHNUM_2
+            0x02aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+            0x02bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb
+            0x02cccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc
+            OP_PUSHNUM_3
+            OP_CHECKMULTISIG
+        ));
+        assert!(check(&syntax).is_empty());
+    }
+
+    #[test]
+    fn check_multisig_mismatch() {
+        let syntax = parse(quote!(
+            OP_PUSHNUM_2
+            0x02aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+            0x02bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb
+            0x02cccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc
+            OP_PUSHNUM_2
+            OP_CHECKMULTISIGVERIFY
+        ));
+        let errors = check(&syntax);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].1,
+            "OP_CHECKMULTISIGVERIFY expects 2 keys, but 3 are pushed"
+        );
+    }
+
+    #[test]
+    fn check_multisig_int() {
+        let syntax = parse(quote!(
+            1
+            0x02aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+            3
+            OP_CHECKMULTISIG
+        ));
+        let errors = check(&syntax);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].1,
+            "OP_CHECKMULTISIG expects 3 keys, but 1 are pushed"
+        );
+    }
+
+    #[test]
+    fn check_multisig_escape() {
+        let syntax = parse(quote!(
+            OP_PUSHNUM_2
+            <key_a>
+            0x02bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb
+            OP_PUSHNUM_3
+            OP_CHECKMULTISIG
+        ));
+        assert!(check(&syntax).is_empty());
+
+        let syntax = parse(quote!(<m> <keys> <n> OP_CHECKMULTISIG));
+        assert!(check(&syntax).is_empty());
+    }
+}
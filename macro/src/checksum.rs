@@ -0,0 +1,122 @@
+use bitcoin::hashes::{hash160, sha256, sha256d, Hash};
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+
+/// A checksum of the script bytes, appended to the script as a final push.
+/// Enabled per invocation with the `@checksum(algorithm, len);` directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checksum {
+    pub algorithm: Algorithm,
+    /// How many leading bytes of the hash are pushed.
+    pub len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Sha256,
+    Sha256d,
+    Hash160,
+}
+
+impl Algorithm {
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        match name {
+            "sha256" => Some(Algorithm::Sha256),
+            "sha256d" => Some(Algorithm::Sha256d),
+            "hash160" => Some(Algorithm::Hash160),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Sha256d => "sha256d",
+            Algorithm::Hash160 => "hash160",
+        }
+    }
+
+    pub fn hash_len(self) -> usize {
+        match self {
+            Algorithm::Sha256 | Algorithm::Sha256d => 32,
+            Algorithm::Hash160 => 20,
+        }
+    }
+
+    fn hash(self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            Algorithm::Sha256 => sha256::Hash::hash(bytes).into_inner().to_vec(),
+            Algorithm::Sha256d => sha256d::Hash::hash(bytes).into_inner().to_vec(),
+            Algorithm::Hash160 => hash160::Hash::hash(bytes).into_inner().to_vec(),
+        }
+    }
+
+    fn path(self) -> TokenStream {
+        match self {
+            Algorithm::Sha256 => quote!(::bitcoin::hashes::sha256::Hash),
+            Algorithm::Sha256d => quote!(::bitcoin::hashes::sha256d::Hash),
+            Algorithm::Hash160 => quote!(::bitcoin::hashes::hash160::Hash),
+        }
+    }
+}
+
+impl Checksum {
+    /// Computes the checksum of a script while the macro is expanded.
+    pub fn compute(&self, script: &[u8]) -> Vec<u8> {
+        let mut hash = self.algorithm.hash(script);
+        hash.truncate(self.len);
+        hash
+    }
+
+    /// Generates a statement which pushes the checksum of the script bytes
+    /// built so far in `script` at runtime, passing the push to the `trace`
+    /// callback first if there is one.
+    pub fn generate(&self, script: &Ident, trace: Option<&Ident>) -> TokenStream {
+        let hash = self.algorithm.path();
+        let len = self.len;
+        let trace = trace.map(|trace| quote!(#trace(checksum.as_bytes());));
+        quote!({
+            let checksum = <#hash as ::bitcoin::hashes::Hash>::into_inner(
+                <#hash as ::bitcoin::hashes::Hash>::hash(&#script),
+            );
+            let checksum = ::bitcoin::blockdata::script::Builder::new()
+                .push_slice(&checksum[..#len])
+                .into_script();
+            #trace
+            #script.extend_from_slice(checksum.as_bytes());
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compute_sha256d() {
+        let checksum = Checksum {
+            algorithm: Algorithm::Sha256d,
+            len: 4,
+        };
+
+        // sha256d("") = 5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456
+        assert_eq!(checksum.compute(&[]), vec![0x5d, 0xf6, 0xe0, 0xe2]);
+    }
+
+    #[test]
+    fn compute_hash160() {
+        let checksum = Checksum {
+            algorithm: Algorithm::Hash160,
+            len: 20,
+        };
+
+        // hash160("") = b472a266d0bd89c13706a4132ccfb16f7c3b9fcb
+        assert_eq!(
+            checksum.compute(&[]),
+            vec![
+                0xb4, 0x72, 0xa2, 0x66, 0xd0, 0xbd, 0x89, 0xc1, 0x37, 0x06, 0xa4, 0x13, 0x2c, 0xcf,
+                0xb1, 0x6f, 0x7c, 0x3b, 0x9f, 0xcb
+            ]
+        );
+    }
+}
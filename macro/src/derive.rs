@@ -0,0 +1,192 @@
+use proc_macro2::{Delimiter, Literal, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::quote;
+
+/// Generates a `Pushable` impl for a struct which pushes each of its fields
+/// in declaration order.
+pub fn derive_pushable(tokens: TokenStream) -> TokenStream {
+    let (name, fields) = parse_struct(tokens);
+    let fields: Vec<TokenStream> = match fields {
+        Fields::Named(names) => names.into_iter().map(|name| quote!(#name)).collect(),
+        Fields::Unnamed(count) => (0..count)
+            .map(|index| {
+                let index = Literal::usize_unsuffixed(index);
+                quote!(#index)
+            })
+            .collect(),
+    };
+
+    quote!(
+        impl ::bitcoin_script::Pushable for #name {
+            fn bitcoin_script_push(
+                &self,
+                builder: ::bitcoin::blockdata::script::Builder,
+            ) -> ::bitcoin::blockdata::script::Builder {
+                #(let builder = ::bitcoin_script::Pushable::bitcoin_script_push(&self.#fields, builder);)*
+                builder
+            }
+
+            fn bitcoin_script_push_be(
+                &self,
+                builder: ::bitcoin::blockdata::script::Builder,
+            ) -> ::bitcoin::blockdata::script::Builder {
+                #(let builder = ::bitcoin_script::Pushable::bitcoin_script_push_be(&self.#fields, builder);)*
+                builder
+            }
+        }
+    )
+}
+
+#[derive(Debug, PartialEq)]
+enum Fields {
+    Named(Vec<proc_macro2::Ident>),
+    Unnamed(usize),
+}
+
+fn parse_struct(tokens: TokenStream) -> (proc_macro2::Ident, Fields) {
+    let mut tokens = skip_attributes(tokens).into_iter();
+
+    match tokens.next() {
+        Some(TokenTree::Ident(ref keyword)) if keyword == "struct" => {}
+        Some(token) => abort!(token.span(), "Pushable can only be derived for structs"),
+        None => abort!(Span::call_site(), "expected a struct"),
+    }
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(name)) => name,
+        _ => abort!(Span::call_site(), "expected a struct name"),
+    };
+
+    let fields = match tokens.next() {
+        Some(TokenTree::Group(fields)) if fields.delimiter() == Delimiter::Brace => Fields::Named(
+            split_fields(fields.stream())
+                .into_iter()
+                .map(|field| match skip_attributes(field).into_iter().next() {
+                    Some(TokenTree::Ident(name)) => name,
+                    _ => abort!(Span::call_site(), "expected a field name"),
+                })
+                .collect(),
+        ),
+        Some(TokenTree::Group(fields)) if fields.delimiter() == Delimiter::Parenthesis => {
+            Fields::Unnamed(split_fields(fields.stream()).len())
+        }
+        Some(TokenTree::Punct(ref punct)) if punct.as_char() == ';' => Fields::Unnamed(0),
+        Some(TokenTree::Punct(ref punct)) if punct.as_char() == '<' => abort!(
+            punct.span(),
+            "Pushable can't be derived for generic structs, implement it instead"
+        ),
+        _ => abort!(Span::call_site(), "expected struct fields"),
+    };
+
+    (name, fields)
+}
+
+// strips leading `#[...]` attributes and a visibility
+fn skip_attributes(tokens: TokenStream) -> TokenStream {
+    let mut tokens = tokens.into_iter().peekable();
+
+    loop {
+        match tokens.peek() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '#' => {
+                tokens.next();
+                tokens.next();
+            }
+            Some(TokenTree::Ident(ident)) if ident == "pub" => {
+                tokens.next();
+                if let Some(TokenTree::Group(group)) = tokens.peek() {
+                    if group.delimiter() == Delimiter::Parenthesis {
+                        tokens.next();
+                    }
+                }
+            }
+            _ => break,
+        }
+    }
+
+    tokens.collect()
+}
+
+// splits the fields at commas outside of generic arguments
+fn split_fields(tokens: TokenStream) -> Vec<TokenStream> {
+    let mut fields = Vec::new();
+    let mut field = TokenStream::new();
+    let mut depth = 0;
+    let mut arrow = false;
+
+    for token in tokens {
+        if let TokenTree::Punct(ref punct) = token {
+            match punct.as_char() {
+                ',' if depth == 0 => {
+                    fields.push(std::mem::take(&mut field));
+                    continue;
+                }
+                '<' => depth += 1,
+                // the `>` of a `->` in a function pointer type is not a
+                // closing bracket
+                '>' if !arrow => depth -= 1,
+                _ => {}
+            }
+            arrow = punct.as_char() == '-';
+        } else {
+            arrow = false;
+        }
+        field.extend(Some(token));
+    }
+    if !field.is_empty() {
+        fields.push(field);
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_struct_named() {
+        let (name, fields) = parse_struct(quote!(
+            #[derive(Pushable)]
+            pub(crate) struct Foo {
+                #[allow(unused)]
+                pub a: BTreeMap<i64, Vec<u8>>,
+                b: fn(u8) -> u8,
+                c: i64,
+            }
+        ));
+
+        assert_eq!(name, "Foo");
+        let names = match fields {
+            Fields::Named(names) => names,
+            _ => panic!(),
+        };
+        assert_eq!(names.len(), 3);
+        assert_eq!(names[0], "a");
+        assert_eq!(names[1], "b");
+        assert_eq!(names[2], "c");
+    }
+
+    #[test]
+    fn parse_struct_unnamed() {
+        let (_, fields) = parse_struct(quote!(
+            struct Foo(pub i64, Vec<u8>);
+        ));
+        assert_eq!(fields, Fields::Unnamed(2));
+
+        let (_, fields) = parse_struct(quote!(
+            struct Foo;
+        ));
+        assert_eq!(fields, Fields::Unnamed(0));
+    }
+
+    #[test]
+    fn derive_pushable_order() {
+        let tokens = derive_pushable(quote!(
+            struct Foo(i64, Vec<u8>);
+        ))
+        .to_string();
+
+        let first = tokens.find("self . 0").unwrap();
+        let second = tokens.find("self . 1").unwrap();
+        assert!(first < second);
+    }
+}
@@ -0,0 +1,110 @@
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use std::iter::Peekable;
+
+/// Evaluates an escape made up only of unsuffixed integer literals, `+`,
+/// `-`, `*`, `/`, `%` and parentheses (e.g. `2016 * 5`), so it can be encoded
+/// while the macro is expanded. Such an escape would be an `i32` when built,
+/// so `None` is returned if any step overflows an `i32` (or divides by zero)
+/// to leave the error to rustc, as well as for anything else.
+pub fn fold_constant(expression: &TokenStream) -> Option<i64> {
+    fold_expression(expression).map(i64::from)
+}
+
+fn fold_expression(expression: &TokenStream) -> Option<i32> {
+    let mut tokens = expression.clone().into_iter().peekable();
+    let value = fold_sum(&mut tokens)?;
+    match tokens.next() {
+        None => Some(value),
+        Some(_) => None,
+    }
+}
+
+type Tokens = Peekable<proc_macro2::token_stream::IntoIter>;
+
+fn fold_sum(tokens: &mut Tokens) -> Option<i32> {
+    let mut value = fold_product(tokens)?;
+    while let Some(op) = next_op(tokens, &['+', '-']) {
+        let rhs = fold_product(tokens)?;
+        value = match op {
+            '+' => value.checked_add(rhs)?,
+            _ => value.checked_sub(rhs)?,
+        };
+    }
+    Some(value)
+}
+
+fn fold_product(tokens: &mut Tokens) -> Option<i32> {
+    let mut value = fold_unary(tokens)?;
+    while let Some(op) = next_op(tokens, &['*', '/', '%']) {
+        let rhs = fold_unary(tokens)?;
+        value = match op {
+            '*' => value.checked_mul(rhs)?,
+            '/' => value.checked_div(rhs)?,
+            _ => value.checked_rem(rhs)?,
+        };
+    }
+    Some(value)
+}
+
+fn fold_unary(tokens: &mut Tokens) -> Option<i32> {
+    if next_op(tokens, &['-']).is_some() {
+        return fold_unary(tokens)?.checked_neg();
+    }
+
+    match tokens.next()? {
+        TokenTree::Literal(literal) => {
+            let literal = literal.to_string().replace('_', "");
+            if !literal.bytes().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            literal.parse().ok()
+        }
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+            fold_expression(&group.stream())
+        }
+        _ => None,
+    }
+}
+
+// takes the next token if it is one of the given operators
+fn next_op(tokens: &mut Tokens, ops: &[char]) -> Option<char> {
+    let op = match tokens.peek()? {
+        TokenTree::Punct(punct) if ops.contains(&punct.as_char()) => punct.as_char(),
+        _ => return None,
+    };
+    tokens.next();
+    Some(op)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn fold_constant_arithmetic() {
+        assert_eq!(fold_constant(&quote!(2016 * 5)), Some(10080));
+        assert_eq!(fold_constant(&quote!(1 + 2 * 3 - 4)), Some(3));
+        assert_eq!(fold_constant(&quote!((1 + 2) * -3)), Some(-9));
+        assert_eq!(fold_constant(&quote!(7 / 2 % 2)), Some(1));
+        assert_eq!(fold_constant(&quote!(-1)), Some(-1));
+        assert_eq!(fold_constant(&quote!(1_000 * 2)), Some(2000));
+    }
+
+    #[test]
+    fn fold_constant_non_constant() {
+        assert_eq!(fold_constant(&quote!(a * 5)), None);
+        assert_eq!(fold_constant(&quote!(5i64)), None);
+        assert_eq!(fold_constant(&quote!(0x10)), None);
+        assert_eq!(fold_constant(&quote!(1 << 4)), None);
+        assert_eq!(fold_constant(&quote!(f(1))), None);
+        assert_eq!(fold_constant(&quote!()), None);
+    }
+
+    #[test]
+    fn fold_constant_overflow() {
+        assert_eq!(fold_constant(&quote!(2147483647 + 1)), None);
+        assert_eq!(fold_constant(&quote!(3000000000)), None);
+        assert_eq!(fold_constant(&quote!(1 / 0)), None);
+    }
+}
@@ -0,0 +1,699 @@
+use super::fold::fold_constant;
+use super::parse::{Directives, Syntax};
+use bitcoin::blockdata::opcodes::all::OP_PUSHNUM_16;
+use bitcoin::blockdata::script::{Builder, Instruction};
+use proc_macro2::{Ident, Literal, Spacing, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+
+pub fn generate(directives: &Directives, syntax: Vec<(Syntax, Span)>) -> TokenStream {
+    let script = Ident::new("script", Span::mixed_site());
+
+    match generate_statements(&script, directives, syntax, Mode::Plain) {
+        Generated::Static(bytes) => {
+            let bytes = Literal::byte_string(&bytes);
+            quote!(::bitcoin::Script::from(#bytes.to_vec()))
+        }
+        Generated::Dynamic(statements) => quote!({
+            let mut #script = ::std::vec::Vec::new();
+            #statements
+            ::bitcoin::Script::from(#script)
+        }),
+    }
+}
+
+/// Like `generate`, but evaluates to a tuple of the script and the byte
+/// range produced by each escape.
+#[cfg(feature = "fragments")]
+pub fn generate_fragments(directives: &Directives, syntax: Vec<(Syntax, Span)>) -> TokenStream {
+    let script = Ident::new("script", Span::mixed_site());
+    let fragments = Ident::new("fragments", Span::mixed_site());
+
+    match generate_statements(&script, directives, syntax, Mode::Fragments(&fragments)) {
+        Generated::Static(bytes) => {
+            let bytes = Literal::byte_string(&bytes);
+            quote!((
+                ::bitcoin::Script::from(#bytes.to_vec()),
+                ::std::vec::Vec::<::std::ops::Range<usize>>::new(),
+            ))
+        }
+        Generated::Dynamic(statements) => quote!({
+            let mut #script = ::std::vec::Vec::new();
+            let mut #fragments = ::std::vec::Vec::<::std::ops::Range<usize>>::new();
+            #statements
+            (::bitcoin::Script::from(#script), #fragments)
+        }),
+    }
+}
+
+/// Like `generate`, but calls `callback` with the encoded bytes of each item
+/// before it is appended to the script.
+pub fn generate_traced(
+    directives: &Directives,
+    callback: TokenStream,
+    syntax: Vec<(Syntax, Span)>,
+) -> TokenStream {
+    let script = Ident::new("script", Span::mixed_site());
+    let trace = Ident::new("trace", Span::mixed_site());
+
+    let statements = match generate_statements(&script, directives, syntax, Mode::Traced(&trace)) {
+        // every item is traced on its own, so only a checksum of an empty
+        // script is left static
+        Generated::Static(bytes) if bytes.is_empty() => TokenStream::new(),
+        Generated::Static(bytes) => {
+            generate_traced_static(&script, &trace, &mut Builder::from(bytes))
+        }
+        Generated::Dynamic(statements) => statements,
+    };
+    quote!({
+        // gives the callback a signature which accepts any lifetime
+        fn traced<F: ::std::ops::FnMut(&[u8])>(callback: F) -> F {
+            callback
+        }
+        #[allow(unused_mut, unused_variables)]
+        let mut #trace = traced(#callback);
+        let mut #script = ::std::vec::Vec::new();
+        #statements
+        ::bitcoin::Script::from(#script)
+    })
+}
+
+/// Generates `NAME`, `NAME_SIZE` and `NAME_OPCODES` constants for a script
+/// without escapes, holding its bytes, its length and the number of
+/// non-push opcodes (as counted against the 201 opcode limit).
+pub fn generate_const(
+    visibility: TokenStream,
+    name: Ident,
+    directives: &Directives,
+    syntax: Vec<(Syntax, Span)>,
+) -> TokenStream {
+    let bytes = generate_bytes(directives, syntax);
+
+    let size = bytes.len();
+    let opcodes = bitcoin::Script::from(bytes.clone())
+        .instructions()
+        .filter(|instruction| match instruction {
+            Ok(Instruction::Op(opcode)) => opcode.to_u8() > OP_PUSHNUM_16.to_u8(),
+            _ => false,
+        })
+        .count();
+
+    let size_name = Ident::new(&format!("{}_SIZE", name), name.span());
+    let opcodes_name = Ident::new(&format!("{}_OPCODES", name), name.span());
+    let bytes = Literal::byte_string(&bytes);
+    quote!(
+        #visibility const #name: &[u8] = #bytes;
+        #visibility const #size_name: usize = #size;
+        #visibility const #opcodes_name: usize = #opcodes;
+    )
+}
+
+/// Encodes a script without escapes while the macro is expanded.
+pub fn generate_bytes(directives: &Directives, syntax: Vec<(Syntax, Span)>) -> Vec<u8> {
+    let script = Ident::new("script", Span::mixed_site());
+    match generate_statements(&script, directives, syntax, Mode::Plain) {
+        Generated::Static(bytes) => bytes,
+        Generated::Dynamic(_) => unreachable!("escapes are rejected before generation"),
+    }
+}
+
+/// Generates a `Vec<::bitcoin_script::ScriptItem>` describing each item, for
+/// external tooling.
+pub fn generate_ast(directives: &Directives, syntax: Vec<(Syntax, Span)>) -> TokenStream {
+    let mut items: Vec<TokenStream> = syntax
+        .into_iter()
+        .map(|(item, span)| {
+            let kind = match item {
+                Syntax::Opcode(opcode) => {
+                    let name = format!("{:?}", opcode);
+                    let byte = opcode.to_u8();
+                    quote!(Opcode {
+                        name: ::std::string::String::from(#name),
+                        byte: #byte,
+                    })
+                }
+                Syntax::Bytes(bytes) => {
+                    let encoded = Builder::new().push_slice(&bytes).into_script();
+                    generate_ast_push(&bytes, encoded.as_bytes())
+                }
+                Syntax::Int(int) if directives.big_endian && !(-1..=16).contains(&int) => {
+                    let data = int.to_be_bytes();
+                    let encoded = Builder::new().push_slice(&data).into_script();
+                    generate_ast_push(&data, encoded.as_bytes())
+                }
+                Syntax::Int(int) => {
+                    let data = Builder::new().push_scriptint(int).into_script();
+                    let data = match data.instructions().next() {
+                        Some(Ok(Instruction::PushBytes(data))) => data.to_vec(),
+                        _ => unreachable!("push_scriptint always pushes data"),
+                    };
+                    let encoded = Builder::new().push_int(int).into_script();
+                    generate_ast_push(&data, encoded.as_bytes())
+                }
+                Syntax::Escape(expression) => generate_ast_escape(quote!(#expression)),
+                Syntax::Builtin { source, .. } => generate_ast_escape(source),
+                Syntax::Byte(expression) => generate_ast_escape(quote!(@byte(#expression))),
+                Syntax::Raw(bytes) => quote!(Raw {
+                    bytes: ::std::vec![#(#bytes),*],
+                }),
+            };
+
+            generate_ast_item(kind, span)
+        })
+        .collect();
+
+    // the checksum isn't written as an item, so it's placed at the invocation
+    if let Some(checksum) = directives.checksum {
+        let algorithm = checksum.algorithm.name();
+        let len = checksum.len;
+        let kind = quote!(Checksum {
+            algorithm: ::std::string::String::from(#algorithm),
+            len: #len,
+        });
+        items.push(generate_ast_item(kind, Span::call_site()));
+    }
+
+    quote!(::std::vec![#(#items),*])
+}
+
+fn generate_ast_item(kind: TokenStream, span: Span) -> TokenStream {
+    let (line, column) = location(span);
+    quote!(::bitcoin_script::ScriptItem {
+        kind: ::bitcoin_script::ScriptItemKind::#kind,
+        span: ::bitcoin_script::SourceSpan {
+            line: #line,
+            column: #column,
+        },
+    })
+}
+
+fn generate_ast_push(data: &[u8], encoded: &[u8]) -> TokenStream {
+    quote!(Push {
+        data: ::std::vec![#(#data),*],
+        encoded: ::std::vec![#(#encoded),*],
+    })
+}
+
+fn generate_ast_escape(expression: TokenStream) -> TokenStream {
+    let source = expression.to_string();
+    quote!(Escape {
+        source: ::std::string::String::from(#source),
+    })
+}
+
+// line and column can only be resolved while the macro is running
+#[cfg(not(test))]
+fn location(span: Span) -> (usize, usize) {
+    let span = span.unwrap();
+    (span.line(), span.column())
+}
+
+#[cfg(test)]
+fn location(_: Span) -> (usize, usize) {
+    (0, 0)
+}
+
+#[derive(Clone, Copy)]
+enum Mode<'a> {
+    Plain,
+    // records the byte range of each escape into the given vec
+    #[cfg_attr(not(feature = "fragments"), allow(dead_code))]
+    Fragments(&'a Ident),
+    // calls the given callback with each item's bytes, one item at a time
+    Traced(&'a Ident),
+}
+
+enum Generated {
+    // every item was encoded while expanding the macro
+    Static(Vec<u8>),
+    // statements appending each item to the script
+    Dynamic(TokenStream),
+}
+
+fn generate_statements(
+    script: &Ident,
+    directives: &Directives,
+    syntax: Vec<(Syntax, Span)>,
+    mode: Mode,
+) -> Generated {
+    // static items are encoded while the macro is expanded, so the generated
+    // code only has to append one byte string per run of static items and
+    // evaluate the escapes in between. this keeps very large scripts cheap
+    // for rustc to compile.
+    let mut statements = TokenStream::new();
+    let mut pending = Builder::new();
+
+    for (item, span) in syntax {
+        // constant escapes like `<2016 * 5>` are encoded like literals, except
+        // when recording fragments, which map each escape to its bytes
+        let item = match item {
+            Syntax::Escape(expression) if !matches!(mode, Mode::Fragments(_)) => {
+                match fold_constant(&expression) {
+                    Some(int) => Syntax::Int(int),
+                    None => Syntax::Escape(expression),
+                }
+            }
+            item => item,
+        };
+
+        let push = match item {
+            Syntax::Opcode(opcode) => {
+                pending = pending.push_opcode(opcode);
+                if let Mode::Traced(trace) = mode {
+                    statements.extend(generate_traced_static(script, trace, &mut pending));
+                }
+                continue;
+            }
+            Syntax::Bytes(bytes) => {
+                pending = pending.push_slice(&bytes);
+                if let Mode::Traced(trace) = mode {
+                    statements.extend(generate_traced_static(script, trace, &mut pending));
+                }
+                continue;
+            }
+            Syntax::Int(int) => {
+                pending = if directives.big_endian && !(-1..=16).contains(&int) {
+                    pending.push_slice(&int.to_be_bytes())
+                } else {
+                    pending.push_int(int)
+                };
+                if let Mode::Traced(trace) = mode {
+                    statements.extend(generate_traced_static(script, trace, &mut pending));
+                }
+                continue;
+            }
+            Syntax::Raw(bytes) => {
+                let mut encoded = std::mem::take(&mut pending).into_script().into_bytes();
+                encoded.extend_from_slice(&bytes);
+                pending = Builder::from(encoded);
+                if let Mode::Traced(trace) = mode {
+                    statements.extend(generate_traced_static(script, trace, &mut pending));
+                }
+                continue;
+            }
+            Syntax::Byte(expression) => generate_byte(expression, span),
+            Syntax::Escape(expression) | Syntax::Builtin { expression, .. } => {
+                generate_escape(expression, directives, span)
+            }
+        };
+
+        statements.extend(generate_static(script, &mut pending));
+        statements.extend(match mode {
+            Mode::Plain => quote_spanned!(span=>
+                #script.extend_from_slice(#push.into_script().as_bytes());
+            ),
+            Mode::Fragments(fragments) => {
+                let start = Ident::new("start", Span::mixed_site());
+                quote_spanned!(span=> {
+                    let #start = #script.len();
+                    #script.extend_from_slice(#push.into_script().as_bytes());
+                    #fragments.push(#start..#script.len());
+                })
+            }
+            Mode::Traced(trace) => {
+                let item = Ident::new("item", Span::mixed_site());
+                quote_spanned!(span=> {
+                    let #item = #push.into_script();
+                    #trace(#item.as_bytes());
+                    #script.extend_from_slice(#item.as_bytes());
+                })
+            }
+        });
+    }
+
+    if statements.is_empty() {
+        let mut bytes = pending.into_script().into_bytes();
+        if let Some(checksum) = directives.checksum {
+            let checksum = Builder::new()
+                .push_slice(&checksum.compute(&bytes))
+                .into_script();
+            bytes.extend_from_slice(checksum.as_bytes());
+        }
+        return Generated::Static(bytes);
+    }
+
+    statements.extend(generate_static(script, &mut pending));
+    if let Some(checksum) = directives.checksum {
+        let trace = match mode {
+            Mode::Traced(trace) => Some(trace),
+            _ => None,
+        };
+        statements.extend(checksum.generate(script, trace));
+    }
+    Generated::Dynamic(statements)
+}
+
+fn generate_static(script: &Ident, pending: &mut Builder) -> TokenStream {
+    if pending.is_empty() {
+        return TokenStream::new();
+    }
+
+    let bytes = std::mem::take(pending).into_script();
+    let bytes = Literal::byte_string(bytes.as_bytes());
+    quote!(#script.extend_from_slice(#bytes);)
+}
+
+fn generate_traced_static(script: &Ident, trace: &Ident, pending: &mut Builder) -> TokenStream {
+    let bytes = std::mem::take(pending).into_script();
+    let bytes = Literal::byte_string(bytes.as_bytes());
+    quote!({
+        #trace(#bytes);
+        #script.extend_from_slice(#bytes);
+    })
+}
+
+fn generate_byte(expression: TokenStream, span: Span) -> TokenStream {
+    quote_spanned!(span=>::bitcoin::blockdata::script::Builder::new().push_slice(&[
+        <u8 as ::std::convert::TryFrom<_>>::try_from(#expression)
+            .expect("byte value out of range (expected 0 to 255)")
+    ]))
+}
+
+fn generate_escape(expression: TokenStream, directives: &Directives, span: Span) -> TokenStream {
+    let push = match (directives.fallible, directives.big_endian) {
+        (false, false) => quote!(Pushable::bitcoin_script_push),
+        (false, true) => quote!(Pushable::bitcoin_script_push_be),
+        (true, false) => quote!(TryPushable::bitcoin_script_try_push),
+        (true, true) => quote!(TryPushable::bitcoin_script_try_push_be),
+    };
+
+    // `<expr: Type>` coerces the expression to the type first, to pick an impl
+    // when inference can't (e.g. for integer literals)
+    let expression = match split_ascription(&expression) {
+        Some((expression, ty)) => {
+            let value = Ident::new("value", Span::mixed_site());
+            quote_spanned!(span=> { let #value: #ty = #expression; #value })
+        }
+        None => expression,
+    };
+
+    let push = quote_spanned!(span=>
+        ::bitcoin_script::#push(
+            &(#expression),
+            ::bitcoin::blockdata::script::Builder::new(),
+        )
+    );
+    if !directives.fallible {
+        return push;
+    }
+
+    // errors are returned as they are, since `TryPushable` already converted
+    // them into the closure's error type (which `?` couldn't infer)
+    let builder = Ident::new("builder", Span::mixed_site());
+    let error = Ident::new("error", Span::mixed_site());
+    quote_spanned!(span=>
+        (match #push {
+            ::std::result::Result::Ok(#builder) => #builder,
+            ::std::result::Result::Err(#error) => return ::std::result::Result::Err(#error),
+        })
+    )
+}
+
+// splits an escape at its first single `:` (as opposed to a `::` path
+// separator), outside of any groups
+fn split_ascription(expression: &TokenStream) -> Option<(TokenStream, TokenStream)> {
+    let tokens: Vec<TokenTree> = expression.clone().into_iter().collect();
+    let is_colon = |token: &TokenTree, spacing| match token {
+        TokenTree::Punct(punct) => punct.as_char() == ':' && punct.spacing() == spacing,
+        _ => false,
+    };
+
+    let index = (0..tokens.len()).find(|&i| {
+        is_colon(&tokens[i], Spacing::Alone)
+            && (i == 0 || !is_colon(&tokens[i - 1], Spacing::Joint))
+    })?;
+    if index == 0 || index == tokens.len() - 1 {
+        return None;
+    }
+
+    Some((
+        tokens[..index].iter().cloned().collect(),
+        tokens[index + 1..].iter().cloned().collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use quote::quote;
+
+    fn assert_tokens_eq(a: TokenStream, b: TokenStream) {
+        let a = format!("{}", a);
+        let b = format!("{}", b);
+        assert_eq!(a, b);
+    }
+
+    fn static_script(bytes: &[u8]) -> TokenStream {
+        let bytes = Literal::byte_string(bytes);
+        quote!(::bitcoin::Script::from(#bytes.to_vec()))
+    }
+
+    #[test]
+    fn generate_empty() {
+        assert_tokens_eq(
+            generate(&Directives::default(), parse(quote!())),
+            static_script(&[]),
+        );
+    }
+
+    #[test]
+    fn generate_opcode() {
+        assert_tokens_eq(
+            generate(&Directives::default(), parse(quote!(OP_CHECKSIGVERIFY))),
+            static_script(&[0xad]),
+        );
+    }
+
+    #[test]
+    fn generate_raw() {
+        assert_tokens_eq(
+            generate(
+                &Directives::default(),
+                parse(quote!(OP_DUP raw 0x4c01 0xab)),
+            ),
+            static_script(&[0x76, 0x4c, 0x01, 0x01, 0xab]),
+        );
+    }
+
+    #[test]
+    fn generate_int() {
+        assert_tokens_eq(
+            generate(
+                &Directives::default(),
+                parse(quote!(
+                    OP_CHECKSIGVERIFY 123 -1 0
+                )),
+            ),
+            static_script(&[0xad, 0x01, 123, 0x4f, 0x00]),
+        );
+    }
+
+    #[test]
+    fn generate_int_zero() {
+        // zero is OP_0 (the empty push), never a push of a zero byte
+        assert_tokens_eq(
+            generate(&Directives::default(), parse(quote!(0 - 0))),
+            static_script(&[0x00, 0x00]),
+        );
+    }
+
+    #[test]
+    fn generate_int_big_endian() {
+        let directives = Directives {
+            big_endian: true,
+            ..Default::default()
+        };
+
+        assert_tokens_eq(
+            generate(&directives, parse(quote!(1234 16 -1 -2))),
+            static_script(&[
+                0x08, 0, 0, 0, 0, 0, 0, 0x04, 0xd2, 0x60, 0x4f, 0x08, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xfe,
+            ]),
+        );
+    }
+
+    #[test]
+    fn generate_hex() {
+        assert_tokens_eq(
+            generate(
+                &Directives::default(),
+                parse(quote!(
+                    OP_CHECKSIGVERIFY 0x01020304
+                )),
+            ),
+            static_script(&[0xad, 0x04, 0x01, 0x02, 0x03, 0x04]),
+        );
+    }
+
+    #[test]
+    fn generate_byte() {
+        let bytes = Literal::byte_string(&[0x01, 0x05]);
+        let push = quote!(::bitcoin::blockdata::script::Builder::new()
+            .push_slice(&[<u8 as ::std::convert::TryFrom<_>>::try_from(n)
+                .expect("byte value out of range (expected 0 to 255)")]));
+
+        assert_tokens_eq(
+            generate(&Directives::default(), parse(quote!(<@byte(5)> <@byte(n)>))),
+            quote!({
+                let mut script = ::std::vec::Vec::new();
+                script.extend_from_slice(#bytes);
+                script.extend_from_slice(#push.into_script().as_bytes());
+                ::bitcoin::Script::from(script)
+            }),
+        );
+    }
+
+    #[test]
+    fn generate_const() {
+        let bytes = Literal::byte_string(&[0x76, 0xa9, 0x55, 0x51, 0x75]);
+
+        assert_tokens_eq(
+            super::generate_const(
+                quote!(pub),
+                Ident::new("FOO", Span::call_site()),
+                &Directives::default(),
+                parse(quote!(OP_DUP OP_HASH160 5 1 OP_DROP)),
+            ),
+            quote!(
+                pub const FOO: &[u8] = #bytes;
+                pub const FOO_SIZE: usize = 5usize;
+                pub const FOO_OPCODES: usize = 3usize;
+            ),
+        );
+    }
+
+    #[test]
+    fn generate_ast() {
+        assert_tokens_eq(
+            super::generate_ast(
+                &Directives::default(),
+                parse(quote!(OP_DUP 0x0102 -1 <foo>)),
+            ),
+            quote!(::std::vec![
+                ::bitcoin_script::ScriptItem {
+                    kind: ::bitcoin_script::ScriptItemKind::Opcode {
+                        name: ::std::string::String::from("OP_DUP"),
+                        byte: 118u8,
+                    },
+                    span: ::bitcoin_script::SourceSpan {
+                        line: 0usize,
+                        column: 0usize,
+                    },
+                },
+                ::bitcoin_script::ScriptItem {
+                    kind: ::bitcoin_script::ScriptItemKind::Push {
+                        data: ::std::vec![1u8, 2u8],
+                        encoded: ::std::vec![2u8, 1u8, 2u8],
+                    },
+                    span: ::bitcoin_script::SourceSpan {
+                        line: 0usize,
+                        column: 0usize,
+                    },
+                },
+                ::bitcoin_script::ScriptItem {
+                    kind: ::bitcoin_script::ScriptItemKind::Push {
+                        data: ::std::vec![129u8],
+                        encoded: ::std::vec![79u8],
+                    },
+                    span: ::bitcoin_script::SourceSpan {
+                        line: 0usize,
+                        column: 0usize,
+                    },
+                },
+                ::bitcoin_script::ScriptItem {
+                    kind: ::bitcoin_script::ScriptItemKind::Escape {
+                        source: ::std::string::String::from("foo"),
+                    },
+                    span: ::bitcoin_script::SourceSpan {
+                        line: 0usize,
+                        column: 0usize,
+                    },
+                }
+            ]),
+        );
+    }
+
+    #[test]
+    fn generate_large() {
+        let mut tokens = TokenStream::new();
+        for _ in 0..10_000 {
+            tokens.extend(quote!(OP_DUP 1234 0x0102 OP_DROP OP_DROP));
+        }
+
+        let syntax = parse(tokens);
+        assert_eq!(syntax.len(), 50_000);
+
+        // however long, a static script is still a single byte string
+        let bytes = [0x76, 0x02, 0xd2, 0x04, 0x02, 0x01, 0x02, 0x75, 0x75].repeat(10_000);
+        assert_tokens_eq(
+            generate(&Directives::default(), syntax),
+            static_script(&bytes),
+        );
+    }
+
+    #[test]
+    fn split_ascription_typed() {
+        let (expression, ty) = split_ascription(&quote!(a::b(c: d) + 1: i64)).unwrap();
+        assert_eq!(expression.to_string(), "a :: b (c : d) + 1");
+        assert_eq!(ty.to_string(), "i64");
+    }
+
+    #[test]
+    fn split_ascription_untyped() {
+        assert!(split_ascription(&quote!(::std::i64::MAX)).is_none());
+        assert!(split_ascription(&quote!(Foo { a: 1 })).is_none());
+    }
+
+    #[test]
+    fn generate_constant_escape() {
+        let bytes = Literal::byte_string(&[0x02, 0x60, 0x27, 0xb2]);
+        assert_tokens_eq(
+            generate(&Directives::default(), parse(quote!(<2016 * 5> OP_CSV))),
+            quote!(::bitcoin::Script::from(#bytes.to_vec())),
+        );
+    }
+
+    #[test]
+    fn generate_escape_fallible() {
+        let directives = Directives {
+            fallible: true,
+            ..Directives::default()
+        };
+        let push = super::generate_escape(quote!(abc), &directives, Span::call_site());
+
+        assert!(push
+            .to_string()
+            .contains(":: bitcoin_script :: TryPushable :: bitcoin_script_try_push (& (abc)"));
+        assert!(push
+            .to_string()
+            .contains("return :: std :: result :: Result :: Err"));
+    }
+
+    #[test]
+    fn generate_escape() {
+        let checksigverify = Literal::byte_string(&[0xad]);
+        let nop = Literal::byte_string(&[0x61]);
+
+        assert_tokens_eq(
+            generate(
+                &Directives::default(),
+                parse(quote!(
+                    OP_CHECKSIGVERIFY <abc> OP_NOP
+                )),
+            ),
+            quote!({
+                let mut script = ::std::vec::Vec::new();
+                script.extend_from_slice(#checksigverify);
+                script.extend_from_slice(::bitcoin_script::Pushable::bitcoin_script_push(
+                    &(abc),
+                    ::bitcoin::blockdata::script::Builder::new(),
+                )
+                .into_script()
+                .as_bytes());
+                script.extend_from_slice(#nop);
+                ::bitcoin::Script::from(script)
+            }),
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use proc_macro2::{Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Reads the script file named by the string literal in `tokens`, returning
+/// its resolved path and its contents as tokens. Relative paths are resolved
+/// from the directory of the file containing the macro invocation, like
+/// `include_str!`.
+pub fn read_include(tokens: TokenStream) -> (PathBuf, TokenStream) {
+    let mut iter = tokens.into_iter();
+    let (literal, span) = match (iter.next(), iter.next()) {
+        (Some(TokenTree::Literal(literal)), None) => {
+            let span = literal.span();
+            (literal.to_string(), span)
+        }
+        (Some(token), _) => abort!(token.span(), "expected a string literal path"),
+        (None, _) => abort!(Span::call_site(), "expected a string literal path"),
+    };
+    let path = match literal.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(path) if !path.contains('\\') => path,
+        _ => abort!(span, "expected a string literal path"),
+    };
+
+    let path = resolve(path);
+    let contents = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+        abort!(span, "couldn't read {}: {}", path.display(), err);
+    });
+    // `include_str!` resolves relative paths itself, so hand it an absolute one
+    let path = path.canonicalize().unwrap_or(path);
+    let tokens = TokenStream::from_str(&contents).unwrap_or_else(|err| {
+        abort!(span, "couldn't tokenize {}: {}", path.display(), err);
+    });
+
+    (path, tokens)
+}
+
+fn resolve(path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        return path;
+    }
+
+    // fall back to the crate root if the invoking file isn't known
+    let dir = proc_macro::Span::call_site()
+        .local_file()
+        .and_then(|file| file.parent().map(PathBuf::from))
+        .or_else(|| std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from))
+        .unwrap_or_default();
+    dir.join(path)
+}
@@ -0,0 +1,243 @@
+//! Procedural macro implementation for the
+//! [`bitcoin-script`](https://crates.io/crates/bitcoin-script) crate. Depend
+//! on `bitcoin-script` rather than using this crate directly.
+
+mod check;
+mod checksum;
+mod derive;
+mod fold;
+mod generate;
+mod include;
+mod lint;
+mod literal;
+mod parse;
+mod script_num;
+mod template;
+mod witness;
+
+use check::check;
+use generate::generate;
+use include::read_include;
+use lint::lint;
+use parse::{
+    parse, parse_callback, parse_const_name, parse_directives, parse_guarded,
+    parse_witness_program, parse_witness_template, Directives, Syntax,
+};
+use proc_macro::TokenStream;
+use proc_macro_error::{abort, emit_error, emit_warning, proc_macro_error, set_dummy};
+use quote::quote;
+use template::extract_holes;
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn bitcoin_script(tokens: TokenStream) -> TokenStream {
+    set_dummy(quote!((::bitcoin::Script::new())));
+    let (directives, syntax) = parse_script(tokens);
+    generate(&directives, syntax).into()
+}
+
+/// Builds the script like `bitcoin_script!`, but evaluates to a
+/// `Result<Script, E>` and allows escapes to use `?` or to evaluate to a
+/// `Result` of a pushable value, whose error is returned. Escapes are
+/// evaluated inside a closure, so `?` (and `return`) exit the macro rather
+/// than the enclosing function. `E` must be known from the context the macro
+/// is used in, and every error returned must convert into it with `From`.
+#[proc_macro]
+#[proc_macro_error]
+pub fn try_bitcoin_script(tokens: TokenStream) -> TokenStream {
+    set_dummy(quote!(
+        (::std::result::Result::Ok(::bitcoin::Script::new()))
+    ));
+    let (mut directives, syntax) = parse_script(tokens);
+    directives.fallible = true;
+    let script = generate(&directives, syntax);
+    quote!((|| ::std::result::Result::Ok(#script))()).into()
+}
+
+/// **Experimental:** builds the script like `bitcoin_script!`, but with its
+/// items in reverse order.
+///
+/// Reversed scripts are generally not valid (e.g. `OP_IF`/`OP_ENDIF` swap
+/// places), this is only intended as a tool for witness-stack ordering
+/// experiments and testing.
+#[proc_macro]
+#[proc_macro_error]
+pub fn bitcoin_script_rev(tokens: TokenStream) -> TokenStream {
+    set_dummy(quote!((::bitcoin::Script::new())));
+    let (directives, mut syntax) = parse_script(tokens);
+    syntax.reverse();
+    generate(&directives, syntax).into()
+}
+
+/// Builds a reusable script template: escapes containing only an identifier
+/// starting with `_` (e.g. `<_pubkey>` or `<_pubkey: PublicKey>`) are holes,
+/// and the macro expands to a closure which takes one argument per hole (in
+/// order of first appearance) and returns the filled-in script.
+#[proc_macro]
+#[proc_macro_error]
+pub fn script_template(tokens: TokenStream) -> TokenStream {
+    set_dummy(quote!((|| ::bitcoin::Script::new())));
+    let (directives, mut syntax) = parse_script(tokens);
+    let params = extract_holes(&mut syntax);
+    let script = generate(&directives, syntax);
+    quote!(|#(#params),*| #script).into()
+}
+
+/// Evaluates to a `Vec<bitcoin_script::ScriptItem>` describing each item of
+/// the script (with opcodes and literals resolved to their encoding, escapes
+/// as their source text, and the source location of each), for use by
+/// external linters and formatters. Escapes are not evaluated.
+#[proc_macro]
+#[proc_macro_error]
+pub fn bitcoin_script_ast(tokens: TokenStream) -> TokenStream {
+    set_dummy(quote!(
+        (::std::vec::Vec::<::bitcoin_script::ScriptItem>::new())
+    ));
+    let (directives, syntax) = parse_script(tokens);
+    generate::generate_ast(&directives, syntax).into()
+}
+
+/// Builds `condition OP_IF script OP_ENDIF` from `guarded!(condition => {
+/// script })`. The condition is any script syntax, usually an escape, and can
+/// be left out along with the `=>` (i.e. `guarded!({ script })`) when the
+/// condition is already on the stack.
+#[proc_macro]
+#[proc_macro_error]
+pub fn guarded(tokens: TokenStream) -> TokenStream {
+    set_dummy(quote!((::bitcoin::Script::new())));
+    let (directives, syntax) = parse_script(parse_guarded(tokens.into()).into());
+    generate(&directives, syntax).into()
+}
+
+/// Builds a script from a file written in the `bitcoin_script!` syntax, read at
+/// compile time. The path is resolved relative to the invoking source file.
+/// Escapes are not supported since the file has no Rust context, but
+/// directives and builtins with literal arguments are.
+#[proc_macro]
+#[proc_macro_error]
+pub fn bitcoin_script_include(tokens: TokenStream) -> TokenStream {
+    set_dummy(quote!((::bitcoin::Script::new())));
+    let (path, tokens) = read_include(tokens.into());
+    let (directives, syntax) = parse_script(tokens.into());
+    reject_escapes(&syntax, "escapes are not supported in included scripts");
+
+    // tracks the file so the script is rebuilt when it changes
+    let path = path.to_string_lossy();
+    let script = generate(&directives, syntax);
+    quote!({
+        const _: &str = include_str!(#path);
+        #script
+    })
+    .into()
+}
+
+/// Defines constants for a script without escapes, for use by tooling which
+/// needs its size at compile time. `bitcoin_script_const!(pub NAME: ...)`
+/// expands to `NAME: &[u8]` holding the script bytes, `NAME_SIZE: usize`
+/// holding its length, and `NAME_OPCODES: usize` holding the number of
+/// non-push opcodes (as counted against the 201 opcode limit).
+#[proc_macro]
+#[proc_macro_error]
+pub fn bitcoin_script_const(tokens: TokenStream) -> TokenStream {
+    let (visibility, name, tokens) = parse_const_name(tokens.into());
+    let (directives, syntax) = parse_script(tokens.into());
+    reject_escapes(
+        &syntax,
+        "escapes are not supported in constant scripts, use bitcoin_script! instead",
+    );
+
+    generate::generate_const(visibility, name, &directives, syntax).into()
+}
+
+/// Builds a P2WSH witness script without escapes, failing to compile unless
+/// the script hashes to the given witness program, as in
+/// `assert_witness_program!(0x<32 bytes>; ...)`. Evaluates to the script.
+#[proc_macro]
+#[proc_macro_error]
+pub fn assert_witness_program(tokens: TokenStream) -> TokenStream {
+    set_dummy(quote!((::bitcoin::Script::new())));
+    let (program, span, tokens) = parse_witness_program(tokens.into());
+    let (directives, syntax) = parse_script(tokens.into());
+    reject_escapes(
+        &syntax,
+        "escapes are not supported in asserted witness scripts",
+    );
+
+    let bytes = generate::generate_bytes(&directives, syntax);
+    if let Err(message) = witness::check_program(&program, &bytes) {
+        abort!(span, message);
+    }
+
+    let bytes = proc_macro2::Literal::byte_string(&bytes);
+    quote!(::bitcoin::Script::from(#bytes.to_vec())).into()
+}
+
+/// Builds a script along with a template of the witness which spends it, as
+/// `script_with_witness_template!([item, ...]; script)`. Evaluates to a tuple
+/// of the `Script` and a `Vec<bitcoin_script::WitnessItem>`, where items
+/// written as `_name` are placeholders (e.g. for signatures made at runtime)
+/// and any other item is a hex literal or an expression evaluating to bytes.
+#[proc_macro]
+#[proc_macro_error]
+pub fn script_with_witness_template(tokens: TokenStream) -> TokenStream {
+    set_dummy(quote!((::bitcoin::Script::new(), ::std::vec::Vec::new())));
+    let (template, tokens) = parse_witness_template(tokens.into());
+    let (directives, syntax) = parse_script(tokens.into());
+    let script = generate(&directives, syntax);
+    quote!((#script, ::std::vec![#(#template),*])).into()
+}
+
+/// **Debugging only:** builds the script like `bitcoin_script!`, but first
+/// takes a callback followed by `;`, which is called with the encoded bytes
+/// of each opcode or push as it is appended. Items are not batched, so this
+/// is slower than `bitcoin_script!` and should not be used outside of
+/// development.
+#[proc_macro]
+#[proc_macro_error]
+pub fn bitcoin_script_traced(tokens: TokenStream) -> TokenStream {
+    set_dummy(quote!((::bitcoin::Script::new())));
+    let (callback, tokens) = parse_callback(tokens.into());
+    let (directives, syntax) = parse_script(tokens.into());
+    generate::generate_traced(&directives, callback, syntax).into()
+}
+
+/// Builds the script like `bitcoin_script!`, but evaluates to a tuple of the
+/// `Script` and a `Vec<Range<usize>>` holding the byte range produced by each
+/// escape, in order. This lets debuggers tell which bytes of a composed
+/// script came from interpolated fragments.
+#[cfg(feature = "fragments")]
+#[proc_macro]
+#[proc_macro_error]
+pub fn bitcoin_script_fragments(tokens: TokenStream) -> TokenStream {
+    set_dummy(quote!((::bitcoin::Script::new(), ::std::vec::Vec::new())));
+    let (directives, syntax) = parse_script(tokens);
+    generate::generate_fragments(&directives, syntax).into()
+}
+
+/// Derives `bitcoin_script::Pushable` for a struct, pushing each of its
+/// fields in declaration order. Every field must itself be `Pushable`.
+#[proc_macro_derive(Pushable)]
+#[proc_macro_error]
+pub fn derive_pushable(tokens: TokenStream) -> TokenStream {
+    derive::derive_pushable(tokens.into()).into()
+}
+
+fn parse_script(tokens: TokenStream) -> (Directives, Vec<(Syntax, proc_macro2::Span)>) {
+    let (directives, tokens) = parse_directives(tokens.into());
+    let syntax = parse(tokens);
+    for (span, error) in check(&syntax) {
+        emit_error!(span, error);
+    }
+    for (span, warning) in lint(&directives.lints, &syntax) {
+        emit_warning!(span, warning);
+    }
+    (directives, syntax)
+}
+
+fn reject_escapes(syntax: &[(Syntax, proc_macro2::Span)], message: &str) {
+    for (item, span) in syntax {
+        if let Syntax::Escape(_) | Syntax::Byte(_) | Syntax::Builtin { .. } = item {
+            abort!(span, message);
+        }
+    }
+}
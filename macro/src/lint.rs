@@ -0,0 +1,387 @@
+use super::parse::Syntax;
+use bitcoin::blockdata::opcodes::{all::*, All as Opcode};
+use proc_macro2::Span;
+
+/// Opt-in heuristic checks over the parsed script, enabled per invocation
+/// with the `@lint(...)` directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lint {
+    /// The script ends on an opcode which consumes the top of the stack
+    /// without leaving a result, e.g. `OP_DROP`.
+    Unterminated,
+    /// The static part of the script could grow the stack beyond the
+    /// 1000-element limit.
+    StackLimit,
+    /// `OP_VERIFY` follows an opcode which has a combined `VERIFY` form, or
+    /// which already verifies its result.
+    RedundantVerify,
+    /// A branch of an `OP_IF`/`OP_NOTIF` conditional contains nothing.
+    EmptyBranch,
+}
+
+impl Lint {
+    pub fn from_name(name: &str) -> Option<Lint> {
+        match name {
+            "unterminated" => Some(Lint::Unterminated),
+            "stack_limit" => Some(Lint::StackLimit),
+            "redundant_verify" => Some(Lint::RedundantVerify),
+            "empty_branch" => Some(Lint::EmptyBranch),
+            _ => None,
+        }
+    }
+}
+
+pub fn lint(lints: &[Lint], syntax: &[(Syntax, Span)]) -> Vec<(Span, String)> {
+    let mut warnings = Vec::new();
+
+    for lint in lints {
+        match lint {
+            Lint::Unterminated => warnings.extend(lint_unterminated(syntax)),
+            Lint::StackLimit => warnings.extend(lint_stack_limit(syntax)),
+            Lint::RedundantVerify => warnings.extend(lint_redundant_verify(syntax)),
+            Lint::EmptyBranch => warnings.extend(lint_empty_branch(syntax)),
+        }
+    }
+
+    warnings
+}
+
+fn lint_unterminated(syntax: &[(Syntax, Span)]) -> Option<(Span, String)> {
+    // only flag opcodes which are known to leave no result behind, anything
+    // else (data, escapes, other opcodes) may well be intentional
+    let (opcode, span) = match syntax.last()? {
+        (Syntax::Opcode(opcode), span) => (*opcode, *span),
+        _ => return None,
+    };
+
+    if !consumes_result(opcode) {
+        return None;
+    }
+
+    Some((
+        span,
+        format!(
+            "script ends with {:?}, which leaves no result to evaluate as true",
+            opcode
+        ),
+    ))
+}
+
+fn consumes_result(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        OP_DROP
+            | OP_2DROP
+            | OP_TOALTSTACK
+            | OP_VERIFY
+            | OP_EQUALVERIFY
+            | OP_NUMEQUALVERIFY
+            | OP_CHECKSIGVERIFY
+            | OP_CHECKMULTISIGVERIFY
+    )
+}
+
+// combined size limit of the main and alt stacks during execution
+const MAX_STACK_SIZE: i64 = 1000;
+
+fn lint_stack_limit(syntax: &[(Syntax, Span)]) -> Option<(Span, String)> {
+    // the depth is relative to whatever the script starts with, so this is
+    // only a lower bound on the real stack size
+    let mut depth: i64 = 0;
+
+    for (item, span) in syntax {
+        let (pops, pushes) = match item {
+            Syntax::Bytes(_) | Syntax::Int(_) | Syntax::Byte(_) => (0, 1),
+            Syntax::Opcode(opcode) => stack_effect(*opcode)?,
+            // the effect of escapes and raw bytes isn't known
+            Syntax::Escape(_) | Syntax::Builtin { .. } | Syntax::Raw(_) => return None,
+        };
+
+        depth += pushes - pops;
+        if depth > MAX_STACK_SIZE {
+            return Some((
+                *span,
+                format!(
+                    "script may exceed the {}-element stack limit",
+                    MAX_STACK_SIZE
+                ),
+            ));
+        }
+    }
+
+    None
+}
+
+// (items popped, items pushed) for opcodes whose effect on the size of the
+// combined stack doesn't depend on the values on it
+fn stack_effect(opcode: Opcode) -> Option<(i64, i64)> {
+    if opcode == OP_PUSHNUM_NEG1
+        || (opcode.to_u8() >= OP_PUSHNUM_1.to_u8() && opcode.to_u8() <= OP_PUSHNUM_16.to_u8())
+    {
+        return Some((0, 1));
+    }
+
+    Some(match opcode {
+        OP_NOP | OP_NOP1 | OP_CLTV | OP_CSV | OP_NOP4 | OP_NOP5 | OP_NOP6 | OP_NOP7 | OP_NOP8
+        | OP_NOP9 | OP_NOP10 | OP_CODESEPARATOR | OP_TOALTSTACK | OP_FROMALTSTACK => (0, 0),
+        OP_DEPTH => (0, 1),
+        OP_DROP | OP_VERIFY => (1, 0),
+        OP_2DROP | OP_EQUALVERIFY | OP_NUMEQUALVERIFY | OP_CHECKSIGVERIFY => (2, 0),
+        OP_DUP | OP_SIZE => (1, 2),
+        OP_2DUP => (2, 4),
+        OP_3DUP => (3, 6),
+        OP_OVER | OP_TUCK => (2, 3),
+        OP_2OVER => (4, 6),
+        OP_NIP => (2, 1),
+        OP_SWAP => (2, 2),
+        OP_2SWAP => (4, 4),
+        OP_ROT => (3, 3),
+        OP_2ROT => (6, 6),
+        OP_1ADD | OP_1SUB | OP_NEGATE | OP_ABS | OP_NOT | OP_0NOTEQUAL | OP_RIPEMD160 | OP_SHA1
+        | OP_SHA256 | OP_HASH160 | OP_HASH256 => (1, 1),
+        OP_EQUAL
+        | OP_ADD
+        | OP_SUB
+        | OP_BOOLAND
+        | OP_BOOLOR
+        | OP_NUMEQUAL
+        | OP_NUMNOTEQUAL
+        | OP_LESSTHAN
+        | OP_GREATERTHAN
+        | OP_LESSTHANOREQUAL
+        | OP_GREATERTHANOREQUAL
+        | OP_MIN
+        | OP_MAX
+        | OP_CHECKSIG => (2, 1),
+        OP_WITHIN | OP_CHECKSIGADD => (3, 1),
+        _ => return None,
+    })
+}
+
+fn lint_redundant_verify(syntax: &[(Syntax, Span)]) -> Vec<(Span, String)> {
+    let mut warnings = Vec::new();
+
+    for pair in syntax.windows(2) {
+        let (previous, (opcode, span)) = match pair {
+            [(Syntax::Opcode(previous), _), (Syntax::Opcode(opcode), span)] => {
+                (*previous, (*opcode, *span))
+            }
+            _ => continue,
+        };
+        if opcode != OP_VERIFY {
+            continue;
+        }
+
+        if let Some(combined) = verify_form(previous) {
+            warnings.push((
+                span,
+                format!(
+                    "{:?} OP_VERIFY can be replaced with {:?}, saving 1 byte",
+                    previous, combined
+                ),
+            ));
+        } else if is_verify(previous) {
+            warnings.push((
+                span,
+                format!(
+                    "OP_VERIFY after {:?} consumes a different stack item, which may be unintended",
+                    previous
+                ),
+            ));
+        }
+    }
+
+    warnings
+}
+
+fn verify_form(opcode: Opcode) -> Option<Opcode> {
+    match opcode {
+        OP_EQUAL => Some(OP_EQUALVERIFY),
+        OP_NUMEQUAL => Some(OP_NUMEQUALVERIFY),
+        OP_CHECKSIG => Some(OP_CHECKSIGVERIFY),
+        OP_CHECKMULTISIG => Some(OP_CHECKMULTISIGVERIFY),
+        _ => None,
+    }
+}
+
+fn is_verify(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        OP_VERIFY | OP_EQUALVERIFY | OP_NUMEQUALVERIFY | OP_CHECKSIGVERIFY | OP_CHECKMULTISIGVERIFY
+    )
+}
+
+fn lint_empty_branch(syntax: &[(Syntax, Span)]) -> Vec<(Span, String)> {
+    let mut warnings = Vec::new();
+    // (span of the OP_IF, opcode starting the current branch, whether the
+    // branch is still empty) for each enclosing conditional
+    let mut branches: Vec<(Span, Opcode, bool)> = Vec::new();
+
+    for (item, span) in syntax {
+        let opcode = match item {
+            Syntax::Opcode(opcode) => Some(*opcode),
+            _ => None,
+        };
+
+        match opcode {
+            Some(OP_ELSE) | Some(OP_ENDIF) => {
+                // unbalanced conditionals are left to the interpreter
+                let (if_span, start, empty) = match branches.pop() {
+                    Some(branch) => branch,
+                    None => continue,
+                };
+                if empty {
+                    warnings.push((if_span, format!("empty {:?} branch", start)));
+                }
+                if opcode == Some(OP_ELSE) {
+                    branches.push((if_span, OP_ELSE, true));
+                }
+            }
+            _ => {
+                if let Some((_, _, empty)) = branches.last_mut() {
+                    *empty = false;
+                }
+                if let Some(opcode @ (OP_IF | OP_NOTIF)) = opcode {
+                    branches.push((*span, opcode, true));
+                }
+            }
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse;
+    use proc_macro2::TokenStream;
+    use quote::quote;
+
+    #[test]
+    fn lint_unterminated_ok() {
+        let syntax = parse(quote!(OP_DUP OP_HASH160 0x0102 OP_EQUALVERIFY OP_CHECKSIG));
+        assert!(lint(&[Lint::Unterminated], &syntax).is_empty());
+    }
+
+    #[test]
+    fn lint_unterminated_escape() {
+        let syntax = parse(quote!(OP_DROP<foo>));
+        assert!(lint(&[Lint::Unterminated], &syntax).is_empty());
+    }
+
+    #[test]
+    fn lint_unterminated_drop() {
+        let syntax = parse(quote!(OP_CHECKSIG OP_DROP));
+        let warnings = lint(&[Lint::Unterminated], &syntax);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].1,
+            "script ends with OP_DROP, which leaves no result to evaluate as true"
+        );
+    }
+
+    #[test]
+    fn lint_unterminated_nip() {
+        // OP_NIP removes the second item, leaving the top one as the result
+        let syntax = parse(quote!(<x> <y> OP_NIP));
+        assert!(lint(&[Lint::Unterminated], &syntax).is_empty());
+    }
+
+    #[test]
+    fn lint_disabled() {
+        let syntax = parse(quote!(OP_CHECKSIG OP_DROP));
+        assert!(lint(&[], &syntax).is_empty());
+    }
+
+    #[test]
+    fn lint_stack_limit_ok() {
+        let mut tokens = quote!(OP_DROP);
+        for _ in 0..1000 {
+            tokens.extend(quote!(1 OP_DUP OP_ADD));
+        }
+        let syntax = parse(tokens);
+        assert!(lint(&[Lint::StackLimit], &syntax).is_empty());
+    }
+
+    #[test]
+    fn lint_stack_limit_exceeded() {
+        let mut tokens = TokenStream::new();
+        for _ in 0..1001 {
+            tokens.extend(quote!(0x01));
+        }
+        let syntax = parse(tokens);
+        let warnings = lint(&[Lint::StackLimit], &syntax);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].1,
+            "script may exceed the 1000-element stack limit"
+        );
+    }
+
+    #[test]
+    fn lint_stack_limit_unmodeled() {
+        let mut tokens = quote!(<foo>);
+        for _ in 0..1001 {
+            tokens.extend(quote!(0x01));
+        }
+        let syntax = parse(tokens);
+        assert!(lint(&[Lint::StackLimit], &syntax).is_empty());
+    }
+
+    #[test]
+    fn lint_redundant_verify_mergeable() {
+        let syntax = parse(quote!(OP_EQUALVERIFY OP_CHECKSIG OP_VERIFY 1));
+        let warnings = lint(&[Lint::RedundantVerify], &syntax);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].1,
+            "OP_CHECKSIG OP_VERIFY can be replaced with OP_CHECKSIGVERIFY, saving 1 byte"
+        );
+    }
+
+    #[test]
+    fn lint_redundant_verify_double() {
+        let syntax = parse(quote!(OP_EQUALVERIFY OP_VERIFY));
+        let warnings = lint(&[Lint::RedundantVerify], &syntax);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].1,
+            "OP_VERIFY after OP_EQUALVERIFY consumes a different stack item, which may be unintended"
+        );
+    }
+
+    #[test]
+    fn lint_empty_branch_if() {
+        let syntax = parse(quote!(OP_IF OP_ELSE OP_DROP OP_ENDIF));
+        let warnings = lint(&[Lint::EmptyBranch], &syntax);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].1, "empty OP_IF branch");
+    }
+
+    #[test]
+    fn lint_empty_branch_else() {
+        let syntax = parse(quote!(OP_NOTIF OP_IF <foo> OP_ENDIF OP_ELSE OP_ENDIF));
+        let warnings = lint(&[Lint::EmptyBranch], &syntax);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].1, "empty OP_ELSE branch");
+    }
+
+    #[test]
+    fn lint_empty_branch_none() {
+        let syntax = parse(quote!(OP_IF OP_IF 1 OP_ENDIF OP_ELSE <foo> OP_ENDIF OP_ENDIF));
+        assert!(lint(&[Lint::EmptyBranch], &syntax).is_empty());
+    }
+
+    #[test]
+    fn lint_redundant_verify_none() {
+        let syntax = parse(quote!(OP_EQUALVERIFY OP_CHECKSIG <foo> OP_VERIFY));
+        assert!(lint(&[Lint::RedundantVerify], &syntax).is_empty());
+    }
+}
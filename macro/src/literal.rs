@@ -0,0 +1,122 @@
+/// Decodes a string literal token, as written in the source (`"..."`, or a
+/// raw string like `r#"..."#`), to its UTF-8 bytes. Byte strings (`b"..."`
+/// and `br"..."`) are decoded to their bytes. Returns `None` if the token
+/// isn't a string literal.
+pub fn decode_str(literal: &str) -> Option<Vec<u8>> {
+    // byte strings can only contain ASCII, and their `\x` escapes are bytes
+    // just like the ASCII-only `\x` escapes of strings, so they decode the
+    // same way once the prefix is removed
+    let literal = literal.strip_prefix('b').unwrap_or(literal);
+
+    if let Some(raw) = literal.strip_prefix('r') {
+        return decode_raw(raw);
+    }
+
+    let body = literal.strip_prefix('"')?.strip_suffix('"')?;
+    unescape(body)
+}
+
+// raw strings have no escapes, only a delimiter of `"` with any number of `#`
+fn decode_raw(raw: &str) -> Option<Vec<u8>> {
+    let hashes = &raw[..raw.len() - raw.trim_start_matches('#').len()];
+    let body = raw[hashes.len()..]
+        .strip_suffix(hashes)?
+        .strip_prefix('"')?
+        .strip_suffix('"')?;
+    Some(body.as_bytes().to_vec())
+}
+
+// the lexer has already rejected invalid escapes, so this only has to decode
+// the valid ones
+fn unescape(body: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                '0' => '\0',
+                c @ ('\\' | '\'' | '"') => c,
+                'x' => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    bytes.push(u8::from_str_radix(&hex, 16).ok()?);
+                    continue;
+                }
+                'u' => {
+                    let hex: String = chars
+                        .by_ref()
+                        .skip(1)
+                        .take_while(|&c| c != '}')
+                        .filter(|&c| c != '_')
+                        .collect();
+                    char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                }
+                // a line continuation skips the newline and leading whitespace
+                '\n' => {
+                    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                    continue;
+                }
+                _ => return None,
+            },
+            c => c,
+        };
+
+        let mut buf = [0; 4];
+        bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_str_plain() {
+        assert_eq!(decode_str(r#""ord""#), Some(b"ord".to_vec()));
+        assert_eq!(
+            decode_str(r#""text/plain;charset=utf-8""#),
+            Some(b"text/plain;charset=utf-8".to_vec())
+        );
+        assert_eq!(decode_str(r#""""#), Some(vec![]));
+    }
+
+    #[test]
+    fn decode_str_escapes() {
+        assert_eq!(
+            decode_str(r#""a\n\t\\\"\x41\0""#),
+            Some(b"a\n\t\\\"A\0".to_vec())
+        );
+        assert_eq!(decode_str(r#""\u{e9}""#), Some("é".as_bytes().to_vec()));
+        assert_eq!(decode_str("\"a\\\n    b\""), Some(b"ab".to_vec()));
+    }
+
+    #[test]
+    fn decode_str_raw() {
+        assert_eq!(decode_str(r##"r"a\n""##), Some(b"a\\n".to_vec()));
+        assert_eq!(
+            decode_str(r###"r#"say "hi""#"###),
+            Some(b"say \"hi\"".to_vec())
+        );
+    }
+
+    #[test]
+    fn decode_byte_str() {
+        assert_eq!(
+            decode_str(r#"b"\x01\x02abc\xff""#),
+            Some(vec![1, 2, b'a', b'b', b'c', 0xff])
+        );
+        assert_eq!(decode_str(r#"br"\x01""#), Some(b"\\x01".to_vec()));
+    }
+
+    #[test]
+    fn decode_str_invalid() {
+        assert_eq!(decode_str("1234"), None);
+        assert_eq!(decode_str("'a'"), None);
+        assert_eq!(decode_str("b'a'"), None);
+    }
+}
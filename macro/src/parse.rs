@@ -0,0 +1,1845 @@
+use super::checksum::{Algorithm, Checksum};
+use super::fold::fold_constant;
+use super::lint::Lint;
+use super::literal::decode_str;
+use super::script_num;
+use bitcoin::blockdata::opcodes::{
+    all::{OP_EQUAL, OP_EQUALVERIFY, OP_PUSHDATA1, OP_PUSHDATA2, OP_PUSHDATA4},
+    All as Opcode,
+};
+use bitcoin::blockdata::script::Instruction;
+use lazy_static::lazy_static;
+use proc_macro2::{
+    Delimiter, Spacing, Span, TokenStream,
+    TokenTree::{self, *},
+};
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::FromStr;
+
+// index opcodes by identifier string
+lazy_static! {
+    static ref OPCODES: HashMap<String, Opcode> = {
+        let mut map = HashMap::with_capacity(256);
+        for i in 0..=255 {
+            let opcode = Opcode::from(i);
+            let name = format!("{:?}", opcode);
+            map.insert(name, opcode);
+        }
+        for (alias, code) in ALIASES {
+            map.insert(alias.to_string(), Opcode::from(*code));
+        }
+        map
+    };
+}
+
+// common alternative names, which don't depend on the names the `bitcoin`
+// crate happens to print for each opcode
+const ALIASES: &[(&str, u8)] = &[
+    ("OP_0", 0x00),
+    ("OP_FALSE", 0x00),
+    ("OP_TRUE", 0x51),
+    ("OP_NOP2", 0xb1),
+    ("OP_CLTV", 0xb1),
+    ("OP_CHECKLOCKTIMEVERIFY", 0xb1),
+    ("OP_NOP3", 0xb2),
+    ("OP_CSV", 0xb2),
+    ("OP_CHECKSEQUENCEVERIFY", 0xb2),
+];
+
+#[derive(Debug)]
+pub enum Syntax {
+    Opcode(Opcode),
+    Escape(TokenStream),
+    Bytes(Vec<u8>),
+    Int(i64),
+    Byte(TokenStream),
+    // bytes appended verbatim, without push framing
+    Raw(Vec<u8>),
+    // an escape generated by a builtin, along with the builtin as written
+    Builtin {
+        source: TokenStream,
+        expression: TokenStream,
+    },
+}
+
+macro_rules! emit_error {
+    ($span:expr, $($message:expr),*) => {{
+        #[cfg(not(test))]
+        proc_macro_error::emit_error!($span, $($message),*);
+
+        #[cfg(test)]
+        {
+            let _ = $span;
+            panic!($($message),*);
+        }
+
+        #[allow(unreachable_code)]
+        {
+            panic!();
+        }
+    }}
+}
+
+macro_rules! abort {
+    ($span:expr, $($message:expr),*) => {{
+        #[cfg(not(test))]
+        proc_macro_error::abort!($span, $($message),*);
+
+        #[cfg(test)]
+        {
+            let _ = $span;
+            panic!($($message),*);
+        }
+    }}
+}
+
+/// Options given at the start of a macro invocation, in the form
+/// `@name(args);`.
+#[derive(Debug, Default)]
+pub struct Directives {
+    pub lints: Vec<Lint>,
+    /// Set by `@be;`: integers which don't fit in a single opcode are pushed
+    /// as 8-byte big-endian data rather than as script numbers.
+    pub big_endian: bool,
+    /// Set by `@checksum(algorithm, len);`.
+    pub checksum: Option<Checksum>,
+    /// Set by `try_bitcoin_script!` rather than a directive: escapes may
+    /// evaluate to a `Result`, whose error is returned from the script.
+    pub fallible: bool,
+}
+
+pub fn parse_directives(tokens: TokenStream) -> (Directives, TokenStream) {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut directives = Directives::default();
+
+    while let Some(Punct(punct)) = tokens.peek() {
+        if punct.as_char() != '@' {
+            break;
+        }
+        let at = tokens.next().unwrap();
+
+        let name = match tokens.next() {
+            Some(Ident(name)) => name,
+            _ => abort!(at.span(), "expected directive name after '@'"),
+        };
+        match name.to_string().as_ref() {
+            "lint" => directives.lints.extend(parse_lints(&name, tokens.next())),
+            "be" => directives.big_endian = true,
+            "checksum" => directives.checksum = Some(parse_checksum(&name, tokens.next())),
+            _ => abort!(name.span(), "unknown directive \"{}\"", name),
+        }
+
+        match tokens.next() {
+            Some(Punct(ref punct)) if punct.as_char() == ';' => {}
+            _ => abort!(name.span(), "expected ';' after directive"),
+        }
+    }
+
+    (directives, tokens.collect())
+}
+
+fn parse_checksum(name: &proc_macro2::Ident, args: Option<TokenTree>) -> Checksum {
+    let usage = "expected checksum algorithm and length, e.g. @checksum(sha256d, 4);";
+    let args: Vec<TokenTree> = match args {
+        Some(Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+            group.stream().into_iter().collect()
+        }
+        _ => abort!(name.span(), "{}", usage),
+    };
+    let (algorithm, len) = match args.as_slice() {
+        [Ident(algorithm), Punct(comma), Literal(len)] if comma.as_char() == ',' => {
+            (algorithm, len)
+        }
+        _ => abort!(name.span(), "{}", usage),
+    };
+
+    let algorithm = Algorithm::from_name(&algorithm.to_string()).unwrap_or_else(|| {
+        abort!(
+            algorithm.span(),
+            "unknown checksum algorithm \"{}\" (expected sha256, sha256d or hash160)",
+            algorithm
+        );
+    });
+    let len = match len.to_string().parse() {
+        Ok(len) if len > 0 && len <= algorithm.hash_len() => len,
+        _ => abort!(
+            len.span(),
+            "checksum length out of range (expected 1 to {})",
+            algorithm.hash_len()
+        ),
+    };
+
+    Checksum { algorithm, len }
+}
+
+fn parse_lints(name: &proc_macro2::Ident, args: Option<TokenTree>) -> Vec<Lint> {
+    let group = match args {
+        Some(Group(group)) if group.delimiter() == Delimiter::Parenthesis => group,
+        _ => abort!(
+            name.span(),
+            "expected list of lints, e.g. @lint(unterminated);"
+        ),
+    };
+
+    group
+        .stream()
+        .into_iter()
+        .filter_map(|token| match token {
+            Ident(ident) => Some(Lint::from_name(&ident.to_string()).unwrap_or_else(|| {
+                abort!(ident.span(), "unknown lint \"{}\"", ident);
+            })),
+            Punct(ref punct) if punct.as_char() == ',' => None,
+            _ => abort!(token.span(), "expected lint name"),
+        })
+        .collect()
+}
+
+/// Splits a leading `expression;` off the tokens, e.g. the callback given to
+/// `bitcoin_script_traced!`.
+pub fn parse_callback(tokens: TokenStream) -> (TokenStream, TokenStream) {
+    let mut tokens = tokens.into_iter();
+    let mut callback = TokenStream::new();
+
+    loop {
+        match tokens.next() {
+            Some(Punct(ref punct)) if punct.as_char() == ';' => break,
+            Some(token) => callback.extend(Some(token)),
+            None => abort!(Span::call_site(), "expected callback followed by ';'"),
+        }
+    }
+    if callback.is_empty() {
+        abort!(Span::call_site(), "expected callback followed by ';'");
+    }
+
+    (callback, tokens.collect())
+}
+
+/// Splits a leading `NAME:` (optionally preceded by a visibility, e.g.
+/// `pub(crate) NAME:`) off the tokens, as given to `bitcoin_script_const!`.
+pub fn parse_const_name(tokens: TokenStream) -> (TokenStream, proc_macro2::Ident, TokenStream) {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut visibility = TokenStream::new();
+
+    if let Some(Ident(ident)) = tokens.peek() {
+        if ident == "pub" {
+            visibility.extend(tokens.next());
+            if let Some(Group(group)) = tokens.peek() {
+                if group.delimiter() == Delimiter::Parenthesis {
+                    visibility.extend(tokens.next());
+                }
+            }
+        }
+    }
+
+    let name = match (tokens.next(), tokens.next()) {
+        (Some(Ident(name)), Some(Punct(ref colon))) if colon.as_char() == ':' => name,
+        _ => abort!(Span::call_site(), "expected constant name followed by ':'"),
+    };
+
+    (visibility, name, tokens.collect())
+}
+
+/// Splits a leading `0x...;` witness program off the tokens, as given to
+/// `assert_witness_program!`.
+pub fn parse_witness_program(tokens: TokenStream) -> (Vec<u8>, Span, TokenStream) {
+    let mut tokens = tokens.into_iter();
+    let program = match (tokens.next(), tokens.next()) {
+        (Some(program @ Literal(_)), Some(Punct(ref semicolon)))
+            if semicolon.as_char() == ';' && program.to_string().starts_with("0x") =>
+        {
+            program
+        }
+        _ => abort!(
+            Span::call_site(),
+            "expected witness program hex literal followed by ';'"
+        ),
+    };
+
+    let span = program.span();
+    let program = match parse_bytes(program) {
+        (Syntax::Bytes(bytes), _) => bytes,
+        _ => unreachable!(),
+    };
+    if program.len() != 32 {
+        abort!(
+            span,
+            "expected a 32-byte witness program, found {} bytes",
+            program.len()
+        );
+    }
+
+    (program, span, tokens.collect())
+}
+
+/// Splits a leading `[item, ...];` witness template off the tokens, as given
+/// to `script_with_witness_template!`. Each item is either `_name` for a
+/// placeholder, a hex literal, or an expression evaluating to bytes, and is
+/// returned as an expression of type `bitcoin_script::WitnessItem`.
+pub fn parse_witness_template(tokens: TokenStream) -> (Vec<TokenStream>, TokenStream) {
+    let mut tokens = tokens.into_iter();
+    let items = match (tokens.next(), tokens.next()) {
+        (Some(Group(items)), Some(Punct(ref semicolon)))
+            if items.delimiter() == Delimiter::Bracket && semicolon.as_char() == ';' =>
+        {
+            items
+        }
+        _ => abort!(
+            Span::call_site(),
+            "expected witness template in brackets followed by ';'"
+        ),
+    };
+
+    let mut template = Vec::new();
+    let mut item = Vec::new();
+    for token in items.stream() {
+        match token {
+            Punct(ref punct) if punct.as_char() == ',' => {
+                template.push(parse_witness_item(std::mem::take(&mut item), items.span()))
+            }
+            token => item.push(token),
+        }
+    }
+    if !item.is_empty() {
+        template.push(parse_witness_item(item, items.span()));
+    }
+
+    (template, tokens.collect())
+}
+
+fn parse_witness_item(tokens: Vec<TokenTree>, span: Span) -> TokenStream {
+    match tokens.as_slice() {
+        [] => abort!(span, "expected witness item"),
+        [Ident(name)] if name.to_string().starts_with('_') => {
+            let name = name.to_string()[1..].to_string();
+            quote::quote!(::bitcoin_script::WitnessItem::Placeholder(
+                ::std::string::String::from(#name)
+            ))
+        }
+        [token @ Literal(_)] if token.to_string().starts_with("0x") => {
+            let bytes = match parse_bytes(token.clone()) {
+                (Syntax::Bytes(bytes), _) => proc_macro2::Literal::byte_string(&bytes),
+                _ => unreachable!(),
+            };
+            quote::quote!(::bitcoin_script::WitnessItem::Bytes(#bytes.to_vec()))
+        }
+        _ => {
+            let expression: TokenStream = tokens.into_iter().collect();
+            quote::quote!(::bitcoin_script::WitnessItem::Bytes(
+                ::std::convert::AsRef::<[u8]>::as_ref(&(#expression)).to_vec()
+            ))
+        }
+    }
+}
+
+/// Rewrites `condition => { body }` (where the condition is optional) into
+/// `condition OP_IF body OP_ENDIF`, as given to `guarded!`.
+pub fn parse_guarded(tokens: TokenStream) -> TokenStream {
+    let mut tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let body = match tokens.pop() {
+        Some(Group(body)) if body.delimiter() == Delimiter::Brace => body,
+        _ => abort!(Span::call_site(), "expected the script in braces"),
+    };
+
+    let condition: TokenStream = match tokens.as_slice() {
+        [] => TokenStream::new(),
+        [condition @ .., Punct(eq), Punct(gt)] if eq.as_char() == '=' && gt.as_char() == '>' => {
+            condition.iter().cloned().collect()
+        }
+        _ => abort!(
+            Span::call_site(),
+            "expected '=>' between the condition and the script"
+        ),
+    };
+    let body = body.stream();
+
+    quote::quote!(#condition OP_IF #body OP_ENDIF)
+}
+
+pub fn parse(tokens: TokenStream) -> Vec<(Syntax, Span)> {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut syntax = Vec::with_capacity(tokens.size_hint().0.max(64));
+
+    while let Some(token) = tokens.next() {
+        syntax.push(match &token {
+            // `raw 0x...`, bytes appended without push framing
+            Ident(ident) if ident == "raw" => parse_raw(ident, tokens.next()),
+
+            // `pushdata1(0x...)` and so on, a push with the given encoding
+            Ident(ident)
+                if matches!(&*ident.to_string(), "pushdata1" | "pushdata2" | "pushdata4")
+                    && matches!(tokens.peek(), Some(Group(group)) if group.delimiter() == Delimiter::Parenthesis) =>
+            {
+                parse_pushdata(ident, tokens.next().unwrap())
+            }
+
+            // `OP_UNKNOWN(byte)`, an opcode by its byte value
+            Ident(ident) if ident == "OP_UNKNOWN" => parse_unknown_opcode(ident, tokens.next()),
+
+            // identifier, look up opcode
+            Ident(ident) => {
+                let (opcode, span) = parse_opcode(ident);
+                match (&opcode, tokens.peek()) {
+                    // an explicit direct push followed by its data
+                    (Syntax::Opcode(opcode), Some(Literal(literal)))
+                        if push_bytes_len(*opcode).is_some()
+                            && literal.to_string().starts_with("0x") =>
+                    {
+                        let literal = literal.clone();
+                        tokens.next();
+                        parse_push_bytes(*opcode, span, literal)
+                    }
+                    _ => (opcode, span),
+                }
+            }
+
+            // '<', start of escape (parse until the matching '>')
+            Punct(punct) if punct.as_char() == '<' => {
+                syntax.extend(parse_escape(token, &mut tokens));
+                continue;
+            }
+
+            // literal, push data (int or bytes)
+            Literal(_) => parse_data(token),
+
+            // negative sign, parse negative int
+            Punct(punct) if punct.as_char() == '-' => parse_negative_int(token, &mut tokens),
+
+            // `$name`, shorthand for `<name>`
+            Punct(punct) if punct.as_char() == '$' => match tokens.next() {
+                Some(Ident(ident)) => {
+                    let span = punct.span().join(ident.span()).unwrap_or(ident.span());
+                    (Syntax::Escape(TokenStream::from(Ident(ident))), span)
+                }
+                _ => abort!(token.span(), "expected an identifier after `$`"),
+            },
+
+            // `{ expr }`, an escape delimited by braces rather than angle
+            // brackets
+            Group(group) if group.delimiter() == Delimiter::Brace => {
+                syntax.extend(parse_escape_contents(group.stream(), group.span()));
+                continue;
+            }
+
+            // '#', start of an attribute (`#[skip] { ... }`, or a doc comment)
+            Punct(punct) if punct.as_char() == '#' => {
+                parse_attribute(token, &mut tokens);
+                continue;
+            }
+
+            // anything else is invalid
+            _ => abort!(token.span(), "unexpected token"),
+        });
+    }
+
+    syntax
+}
+
+// the data length of `OP_PUSHBYTES_1` to `OP_PUSHBYTES_75`
+fn push_bytes_len(opcode: Opcode) -> Option<usize> {
+    match opcode.to_u8() {
+        len @ 1..=75 => Some(len as usize),
+        _ => None,
+    }
+}
+
+// `OP_PUSHBYTES_N 0x...` pushes the data with exactly that opcode, which has
+// to match the length of the data. anything other than a hex literal after
+// the opcode leaves it as a lone opcode, as before.
+fn parse_push_bytes(opcode: Opcode, span: Span, literal: proc_macro2::Literal) -> (Syntax, Span) {
+    let (data, data_span) = parse_bytes(Literal(literal));
+    let len = push_bytes_len(opcode).unwrap();
+    match &data {
+        Syntax::Bytes(bytes) if bytes.len() != len => emit_error!(
+            data_span,
+            "{:?} expects {} bytes of data, found {}",
+            opcode,
+            len,
+            bytes.len()
+        ),
+        _ => {}
+    }
+
+    (data, span)
+}
+
+// `#[skip] { ... }` is parsed so its contents stay valid, but is left out of
+// the script. doc comments (`/// ...` or `/** ... */`) reach the macro as
+// `#[doc = "..."]` attributes, and are dropped like ordinary comments.
+fn parse_attribute<T>(token: TokenTree, tokens: &mut T)
+where
+    T: Iterator<Item = TokenTree>,
+{
+    match tokens.next() {
+        Some(Group(attribute))
+            if attribute.delimiter() == Delimiter::Bracket
+                && attribute.stream().to_string() == "skip" => {}
+        Some(Group(attribute))
+            if attribute.delimiter() == Delimiter::Bracket
+                && matches!(
+                    attribute.stream().into_iter().next(),
+                    Some(Ident(ident)) if ident == "doc"
+                ) =>
+        {
+            return;
+        }
+        _ => abort!(token.span(), "expected #[skip]"),
+    }
+
+    match tokens.next() {
+        Some(Group(group)) if group.delimiter() == Delimiter::Brace => {
+            parse(group.stream());
+        }
+        _ => abort!(token.span(), "expected braces after #[skip]"),
+    }
+}
+
+fn parse_opcode(ident: &proc_macro2::Ident) -> (Syntax, Span) {
+    let name = ident.to_string();
+    let opcode = lookup_opcode(&name).unwrap_or_else(|| {
+        emit_error!(ident.span(), "unknown opcode \"{}\"", name);
+    });
+    (Syntax::Opcode(opcode), ident.span())
+}
+
+// `raw 0x...` appends the bytes as they are, e.g. for a pre-encoded script
+// fragment or a hand-crafted push
+fn parse_raw(ident: &proc_macro2::Ident, data: Option<TokenTree>) -> (Syntax, Span) {
+    match data {
+        Some(token @ Literal(_)) if token.to_string().starts_with("0x") => {
+            let span = ident.span().join(token.span()).unwrap_or(token.span());
+            match parse_bytes(token) {
+                (Syntax::Bytes(bytes), _) => (Syntax::Raw(bytes), span),
+                _ => unreachable!("hex literals are parsed as bytes"),
+            }
+        }
+        _ => abort!(ident.span(), "expected a hex literal after `raw`"),
+    }
+}
+
+// `pushdata1(0x...)`, `pushdata2(0x...)` and `pushdata4(0x...)` push the data
+// with that opcode even when a shorter encoding exists, for protocols and tests
+// which need a specific framing
+fn parse_pushdata(ident: &proc_macro2::Ident, args: TokenTree) -> (Syntax, Span) {
+    let span = ident.span().join(args.span()).unwrap_or(args.span());
+    let mut tokens = match args {
+        Group(group) => group.stream().into_iter(),
+        _ => unreachable!("checked by the caller"),
+    };
+    let data = match (tokens.next(), tokens.next()) {
+        (Some(token @ Literal(_)), None) => match parse_data(token) {
+            (Syntax::Bytes(bytes), _) => bytes,
+            _ => abort!(span, "expected a hex or string literal"),
+        },
+        _ => abort!(span, "expected a hex or string literal"),
+    };
+
+    let len = data.len();
+    let mut encoded = match &*ident.to_string() {
+        "pushdata1" if len <= u8::MAX as usize => vec![OP_PUSHDATA1.to_u8(), len as u8],
+        "pushdata2" if len <= u16::MAX as usize => {
+            let mut encoded = vec![OP_PUSHDATA2.to_u8()];
+            encoded.extend_from_slice(&(len as u16).to_le_bytes());
+            encoded
+        }
+        "pushdata4" if len <= u32::MAX as usize => {
+            let mut encoded = vec![OP_PUSHDATA4.to_u8()];
+            encoded.extend_from_slice(&(len as u32).to_le_bytes());
+            encoded
+        }
+        name => abort!(span, "too much data for {} ({} bytes)", name, len),
+    };
+    encoded.extend_from_slice(&data);
+    (Syntax::Raw(encoded), span)
+}
+
+// `OP_UNKNOWN(0xba)` appends the opcode with the given byte value, for opcodes
+// which have no name in the `bitcoin` crate (e.g. proposed opcodes or
+// OP_SUCCESSx)
+fn parse_unknown_opcode(ident: &proc_macro2::Ident, args: Option<TokenTree>) -> (Syntax, Span) {
+    let usage = "expected an opcode byte, e.g. OP_UNKNOWN(0xba)";
+    let (literal, span) = match args {
+        Some(Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+            let mut tokens = group.stream().into_iter();
+            match (tokens.next(), tokens.next()) {
+                (Some(Literal(literal)), None) => (literal.to_string(), group.span()),
+                _ => abort!(group.span(), "{}", usage),
+            }
+        }
+        _ => abort!(ident.span(), "{}", usage),
+    };
+
+    let byte = match literal.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => literal.parse(),
+    };
+    let byte = byte.unwrap_or_else(|_| abort!(span, "{}", usage));
+    let span = ident.span().join(span).unwrap_or(span);
+    (Syntax::Opcode(Opcode::from(byte)), span)
+}
+
+// scripts copied from other tooling (e.g. btcdeb or `decodescript`) often
+// leave out the `OP_` prefix or use another case, as in `checksig` or
+// `CheckSig`, so names are also looked up in upper case with the prefix added
+fn lookup_opcode(name: &str) -> Option<Opcode> {
+    if let Some(opcode) = OPCODES.get(name) {
+        return Some(*opcode);
+    }
+
+    // the name is upper-cased into a buffer on the stack, which is long
+    // enough for every opcode name (the longest is 22 bytes)
+    let mut buffer = [0u8; 32];
+    let start = match name.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("OP_") => 0,
+        _ => 3,
+    };
+    let len = start + name.len();
+    if len > buffer.len() || !name.is_ascii() {
+        return None;
+    }
+    buffer[..3].copy_from_slice(b"OP_");
+    buffer[start..len].copy_from_slice(name.as_bytes());
+    buffer[..len].make_ascii_uppercase();
+    let name = std::str::from_utf8(&buffer[..len]).ok()?;
+    OPCODES.get(name).copied()
+}
+
+// the escape ends at the first `>` which doesn't close a generic argument
+// list opened inside it (e.g. `Vec::<u8>::new()`), isn't a `>>` shift, and
+// isn't part of `->`, `=>` or `>=`. a lone `<` is taken to open generics, so
+// comparisons have to be wrapped in parentheses, as in `<(a < b)>`.
+fn parse_escape<T>(token: TokenTree, tokens: &mut Peekable<T>) -> Vec<(Syntax, Span)>
+where
+    T: Iterator<Item = TokenTree>,
+{
+    let mut escape = TokenStream::new();
+    let mut span = token.span();
+    let mut depth = 0;
+    let mut previous: Option<proc_macro2::Punct> = None;
+
+    loop {
+        let token = tokens
+            .next()
+            .unwrap_or_else(|| abort!(token.span(), "unterminated escape"));
+
+        span = span.join(token.span()).unwrap_or(token.span());
+
+        if let Punct(punct) = &token {
+            // the first punct of `<<`, `>>`, `<=`, `->` and so on is joint
+            let joint = |tokens: &mut Peekable<T>, c: char| match tokens.peek() {
+                Some(Punct(next)) => punct.spacing() == Spacing::Joint && next.as_char() == c,
+                _ => false,
+            };
+            let follows = match &previous {
+                Some(previous) if previous.spacing() == Spacing::Joint => {
+                    previous.as_char() == '-' || previous.as_char() == '='
+                }
+                _ => false,
+            };
+
+            let shift = match punct.as_char() {
+                '<' if joint(tokens, '<') => true,
+                '<' if joint(tokens, '=') => false,
+                '<' => {
+                    depth += 1;
+                    false
+                }
+                '>' if follows || joint(tokens, '=') => false,
+                '>' if depth > 0 => {
+                    depth -= 1;
+                    false
+                }
+                '>' if joint(tokens, '>') => true,
+                // end of escape
+                '>' => break,
+                _ => false,
+            };
+
+            // shifts are taken as a whole
+            if shift {
+                escape.extend(TokenStream::from(token));
+                escape.extend(tokens.next().map(TokenStream::from));
+                previous = None;
+                continue;
+            }
+            previous = Some(punct.clone());
+        } else {
+            previous = None;
+        }
+
+        escape.extend(TokenStream::from(token));
+    }
+
+    parse_escape_contents(escape, span)
+}
+
+fn parse_escape_contents(escape: TokenStream, span: Span) -> Vec<(Syntax, Span)> {
+    parse_builtin(&escape, span).unwrap_or_else(|| vec![(Syntax::Escape(escape), span)])
+}
+
+// escapes of the form `<@name(args)>` where `name` is one of the builtins
+// below are handled by the macro rather than by the `Pushable` impls. the `@`
+// keeps them apart from calls to Rust functions which share a builtin's name
+fn parse_builtin(escape: &TokenStream, span: Span) -> Option<Vec<(Syntax, Span)>> {
+    let mut tokens = escape.clone().into_iter();
+    let (name, args) = match (tokens.next(), tokens.next(), tokens.next(), tokens.next()) {
+        (Some(Punct(at)), Some(Ident(name)), Some(Group(args)), None)
+            if at.as_char() == '@' && args.delimiter() == Delimiter::Parenthesis =>
+        {
+            (name, args)
+        }
+        _ => return None,
+    };
+
+    let name = name.to_string();
+    let syntax = match name.as_ref() {
+        "byte" => vec![(parse_byte(args.stream(), span), span)],
+        "then" => parse_then(args.stream(), span),
+        "addr" => parse_addr(args.stream(), span),
+        "instructions" => vec![(parse_instructions(args.stream()), span)],
+        "redeem" | "push_script" => vec![(parse_redeem(args.stream()), span)],
+        "eq" => parse_compare(args.stream(), OP_EQUAL, span),
+        "eqverify" => parse_compare(args.stream(), OP_EQUALVERIFY, span),
+        "pushnum" => vec![(parse_pushnum(args.stream(), span), span)],
+        "le32" => vec![(parse_le(args.stream(), 4, span), span)],
+        "le64" => vec![(parse_le(args.stream(), 8, span), span)],
+        "scriptnum" => vec![(parse_scriptnum(args.stream(), span), span)],
+        _ => abort!(span, "unknown builtin @{}", name),
+    };
+
+    // builtins which generate their own escape keep what was written, so
+    // tooling sees the builtin rather than the generated code
+    if !matches!(
+        name.as_ref(),
+        "addr"
+            | "instructions"
+            | "redeem"
+            | "push_script"
+            | "pushnum"
+            | "le32"
+            | "le64"
+            | "scriptnum"
+    ) {
+        return Some(syntax);
+    }
+    Some(
+        syntax
+            .into_iter()
+            .map(|(item, span)| match item {
+                Syntax::Escape(expression) => (
+                    Syntax::Builtin {
+                        source: escape.clone(),
+                        expression,
+                    },
+                    span,
+                ),
+                item => (item, span),
+            })
+            .collect(),
+    )
+}
+
+fn parse_byte(args: TokenStream, span: Span) -> Syntax {
+    let mut tokens = args.clone().into_iter();
+    if let (Some(Literal(literal)), None) = (tokens.next(), tokens.next()) {
+        let value = parse_int_literal(&literal.to_string()).unwrap_or_else(|| {
+            emit_error!(span, "invalid integer literal for @byte");
+        });
+        if value > u8::MAX as u64 {
+            emit_error!(span, "byte value out of range (expected 0 to 255)");
+        }
+        return Syntax::Bytes(vec![value as u8]);
+    }
+
+    Syntax::Byte(args)
+}
+
+// `@then(value, [OP_X, OP_Y])` pushes the value, followed by the opcodes
+fn parse_then(args: TokenStream, span: Span) -> Vec<(Syntax, Span)> {
+    let mut args: Vec<TokenTree> = args.into_iter().collect();
+    let opcodes = match (args.pop(), args.pop()) {
+        (Some(Group(opcodes)), Some(Punct(comma)))
+            if opcodes.delimiter() == Delimiter::Bracket && comma.as_char() == ',' =>
+        {
+            opcodes
+        }
+        _ => abort!(span, "expected @then(value, [opcodes])"),
+    };
+    if args.is_empty() {
+        abort!(span, "expected @then(value, [opcodes])");
+    }
+
+    let value: TokenStream = args.into_iter().collect();
+    let mut syntax =
+        parse_builtin(&value, span).unwrap_or_else(|| vec![(Syntax::Escape(value), span)]);
+
+    for token in opcodes.stream() {
+        match token {
+            Ident(ident) => syntax.push(parse_opcode(&ident)),
+            Punct(ref punct) if punct.as_char() == ',' => {}
+            _ => abort!(token.span(), "expected opcode"),
+        }
+    }
+
+    syntax
+}
+
+// `@addr("...")` appends the scriptPubKey of the address, which is parsed at
+// compile time for string literals and at runtime otherwise
+fn parse_addr(args: TokenStream, span: Span) -> Vec<(Syntax, Span)> {
+    let mut tokens = args.clone().into_iter();
+    let literal = match (tokens.next(), tokens.next()) {
+        (Some(Literal(literal)), None) => literal.to_string(),
+        (Some(_), _) => {
+            let escape = quote::quote!(
+                <::bitcoin::Address as ::std::str::FromStr>::from_str(#args)
+                    .expect("invalid address")
+                    .script_pubkey()
+            );
+            return vec![(Syntax::Escape(escape), span)];
+        }
+        (None, _) => abort!(span, "expected @addr(address)"),
+    };
+
+    let address = match literal.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(address) => address,
+        None => abort!(span, "expected address string literal"),
+    };
+    let script = match bitcoin::Address::from_str(address) {
+        Ok(address) => address.script_pubkey(),
+        Err(err) => abort!(span, "invalid address \"{}\" ({})", address, err),
+    };
+
+    // address scripts only use minimal pushes, so pushing each instruction
+    // again reproduces the exact scriptPubKey
+    script
+        .instructions()
+        .map(|instruction| match instruction {
+            Ok(Instruction::Op(opcode)) => (Syntax::Opcode(opcode), span),
+            Ok(Instruction::PushBytes(bytes)) => (Syntax::Bytes(bytes.to_vec()), span),
+            Err(_) => unreachable!("address scripts are well-formed"),
+        })
+        .collect()
+}
+
+// `@instructions(bytes)` appends raw script bytes without push framing, by
+// interpolating them as a `Script`
+fn parse_instructions(args: TokenStream) -> Syntax {
+    Syntax::Escape(quote::quote!(::bitcoin::Script::from(
+        ::std::convert::AsRef::<[u8]>::as_ref(&(#args)).to_vec()
+    )))
+}
+
+// `@redeem(script)` (or `@push_script(script)`) pushes the serialized script as
+// a single data element (e.g. a P2SH redeem script), rather than appending its
+// instructions
+fn parse_redeem(args: TokenStream) -> Syntax {
+    Syntax::Escape(quote::quote!(::bitcoin::Script::as_bytes(&(#args))))
+}
+
+// `@pushnum(value, width)` pushes the value as a script number padded to exactly
+// `width` bytes, at compile time if the value is a constant
+fn parse_pushnum(args: TokenStream, span: Span) -> Syntax {
+    let usage = "expected @pushnum(value, width)";
+    let mut args: Vec<TokenTree> = args.into_iter().collect();
+    let width = match (args.pop(), args.pop()) {
+        (Some(Literal(width)), Some(Punct(comma))) if comma.as_char() == ',' => width,
+        _ => abort!(span, "{}", usage),
+    };
+    if args.is_empty() {
+        abort!(span, "{}", usage);
+    }
+    let width = match parse_int_literal(&width.to_string()) {
+        Some(width @ 1..=8) => width as usize,
+        _ => abort!(span, "invalid width for pushnum (expected 1 to 8)"),
+    };
+
+    let value: TokenStream = args.into_iter().collect();
+    if let Some(value) = fold_constant(&value) {
+        return match script_num::padded(value, width) {
+            Some(bytes) => Syntax::Bytes(bytes),
+            None => abort!(
+                span,
+                "{} doesn't fit in a {}-byte script number",
+                value,
+                width
+            ),
+        };
+    }
+
+    Syntax::Escape(quote::quote!(
+        ::bitcoin_script::ScriptNum(#value)
+            .to_padded_bytes(#width)
+            .expect("value doesn't fit in the pushnum width")
+    ))
+}
+
+// `@le32(value)` and `@le64(value)` push the value as little-endian bytes of
+// that width (e.g. for CTV and introspection gadgets), at compile time if the
+// value is a constant
+fn parse_le(args: TokenStream, width: usize, span: Span) -> Syntax {
+    if args.is_empty() {
+        abort!(span, "expected a value to encode");
+    }
+
+    if let Some(value) = fold_constant(&args) {
+        if value < 0 {
+            abort!(span, "value out of range (expected an unsigned integer)");
+        }
+        return Syntax::Bytes((value as u64).to_le_bytes()[..width].to_vec());
+    }
+
+    let ty = proc_macro2::Ident::new(if width == 4 { "u32" } else { "u64" }, span);
+    Syntax::Escape(quote::quote!(::std::primitive::#ty::to_le_bytes(#args)))
+}
+
+// `@scriptnum(value)` pushes the value as a minimal script number data element
+// even where an opcode could push it (as `ScriptNum` does), and
+// `@scriptnum(value, width)` also checks that it takes at most `width` bytes
+fn parse_scriptnum(args: TokenStream, span: Span) -> Syntax {
+    let mut args: Vec<TokenTree> = args.into_iter().collect();
+    let width = match args.as_slice() {
+        [.., Punct(comma), Literal(width)] if comma.as_char() == ',' => {
+            let width = match parse_int_literal(&width.to_string()) {
+                Some(width @ 1..=8) => width as usize,
+                _ => abort!(span, "invalid width for scriptnum (expected 1 to 8)"),
+            };
+            args.truncate(args.len() - 2);
+            Some(width)
+        }
+        _ => None,
+    };
+    if args.is_empty() {
+        abort!(
+            span,
+            "expected @scriptnum(value) or @scriptnum(value, width)"
+        );
+    }
+
+    let value: TokenStream = args.into_iter().collect();
+    if let Some(value) = fold_constant(&value) {
+        let bytes = script_num::minimal(value);
+        if let Some(width) = width.filter(|&width| bytes.len() > width) {
+            abort!(
+                span,
+                "{} doesn't fit in a {}-byte script number",
+                value,
+                width
+            );
+        }
+        return Syntax::Bytes(bytes);
+    }
+
+    let num = quote::quote!(::bitcoin_script::ScriptNum(#value));
+    let width = match width {
+        Some(width) => width,
+        None => return Syntax::Escape(num),
+    };
+    let bytes = proc_macro2::Ident::new("bytes", Span::mixed_site());
+    Syntax::Escape(quote::quote!({
+        let #bytes = #num.to_bytes();
+        if #bytes.len() > #width {
+            ::core::panic!("value doesn't fit in a {}-byte script number", #width);
+        }
+        #bytes
+    }))
+}
+
+// `@eq(value)` and `@eqverify(value)` push the value, followed by the opcode
+fn parse_compare(args: TokenStream, opcode: Opcode, span: Span) -> Vec<(Syntax, Span)> {
+    if args.is_empty() {
+        abort!(span, "expected a value to compare with");
+    }
+
+    let mut syntax =
+        parse_builtin(&args, span).unwrap_or_else(|| vec![(Syntax::Escape(args), span)]);
+    syntax.push((Syntax::Opcode(opcode), span));
+    syntax
+}
+
+fn parse_data(token: TokenTree) -> (Syntax, Span) {
+    if token.to_string().starts_with("0x") {
+        parse_bytes(token)
+    } else if token.to_string().starts_with("0b") {
+        parse_binary(token)
+    } else if token.to_string().starts_with(&['"', 'r', 'b'][..]) {
+        parse_string(token)
+    } else {
+        parse_int(token, false)
+    }
+}
+
+// string literals are pushed as their UTF-8 bytes (e.g. for inscription
+// envelopes), and byte strings as their bytes
+fn parse_string(token: TokenTree) -> (Syntax, Span) {
+    let bytes = decode_str(&token.to_string()).unwrap_or_else(|| {
+        emit_error!(token.span(), "invalid string literal");
+    });
+    (Syntax::Bytes(bytes), token.span())
+}
+
+// `0b` literals are pushed as bytes like hex literals, so they need a whole
+// number of bytes, 8 digits each
+fn parse_binary(token: TokenTree) -> (Syntax, Span) {
+    let digits = token.to_string()[2..].replace('_', "");
+    if digits.is_empty()
+        || !digits.len().is_multiple_of(8)
+        || !digits.bytes().all(|c| c == b'0' || c == b'1')
+    {
+        emit_error!(
+            token.span(),
+            "invalid binary literal (expected a multiple of 8 binary digits)"
+        );
+    }
+
+    let bytes = digits
+        .as_bytes()
+        .chunks(8)
+        .map(|byte| {
+            byte.iter()
+                .fold(0u8, |value, digit| (value << 1) | (digit - b'0'))
+        })
+        .collect();
+    (Syntax::Bytes(bytes), token.span())
+}
+
+fn parse_bytes(token: TokenTree) -> (Syntax, Span) {
+    // `_` separators are ignored, e.g. `0xdead_beef`
+    let hex_bytes = token.to_string()[2..].replace('_', "");
+    let bytes = hex::decode(hex_bytes).unwrap_or_else(|err| {
+        emit_error!(token.span(), "invalid hex literal ({})", err);
+    });
+    (Syntax::Bytes(bytes), token.span())
+}
+
+fn parse_int(token: TokenTree, negative: bool) -> (Syntax, Span) {
+    // `_` separators are allowed as in Rust, e.g. `1_000_000`
+    let token_str = token.to_string().replace('_', "");
+    let n: i64 = token_str.parse().unwrap_or_else(|err| {
+        emit_error!(token.span(), "invalid number literal ({})", err);
+    });
+    let n = if negative { -n } else { n };
+    (Syntax::Int(n), token.span())
+}
+
+// reads an unsigned integer literal as Rust would, with `_` separators, a
+// `0x`, `0o` or `0b` prefix and a type suffix (e.g. `5u8`) all allowed
+fn parse_int_literal(literal: &str) -> Option<u64> {
+    const SUFFIXES: [&str; 12] = [
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+    ];
+
+    let literal = literal.replace('_', "");
+    let literal = SUFFIXES
+        .iter()
+        .find_map(|suffix| literal.strip_suffix(suffix))
+        .unwrap_or(&literal);
+    let (digits, radix) = match literal.get(..2) {
+        Some("0x") => (&literal[2..], 16),
+        Some("0o") => (&literal[2..], 8),
+        Some("0b") => (&literal[2..], 2),
+        _ => (literal, 10),
+    };
+    if !digits.bytes().all(|c| (c as char).is_digit(radix)) {
+        return None;
+    }
+    u64::from_str_radix(digits, radix).ok()
+}
+
+fn parse_negative_int<T>(token: TokenTree, tokens: &mut T) -> (Syntax, Span)
+where
+    T: Iterator<Item = TokenTree>,
+{
+    let fail = || {
+        #[allow(unused_variables)]
+        let span = token.span();
+        emit_error!(
+            span,
+            "expected negative sign to be followed by number literal"
+        );
+    };
+
+    let maybe_token = tokens.next();
+
+    if let Some(token) = maybe_token {
+        if let Literal(_) = token {
+            parse_int(token, true)
+        } else {
+            fail()
+        }
+    } else {
+        fail()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::blockdata::opcodes::all as opcodes;
+    use proc_macro2::TokenTree;
+    use quote::quote;
+
+    #[test]
+    fn parse_directives_none() {
+        let (directives, tokens) = parse_directives(quote!(OP_CHECKSIG));
+        assert!(directives.lints.is_empty());
+        assert_eq!(tokens.to_string(), "OP_CHECKSIG");
+    }
+
+    #[test]
+    fn parse_directives_lint() {
+        let (directives, tokens) = parse_directives(quote!(@lint(unterminated); OP_CHECKSIG));
+        assert_eq!(directives.lints, vec![Lint::Unterminated]);
+        assert_eq!(tokens.to_string(), "OP_CHECKSIG");
+    }
+
+    #[test]
+    fn parse_directives_be() {
+        let (directives, tokens) = parse_directives(quote!(@be; @lint(unterminated); 1234));
+        assert!(directives.big_endian);
+        assert_eq!(directives.lints, vec![Lint::Unterminated]);
+        assert_eq!(tokens.to_string(), quote!(1234).to_string());
+    }
+
+    #[test]
+    fn parse_directives_checksum() {
+        let (directives, _) = parse_directives(quote!(@checksum(hash160, 8); OP_DUP));
+        assert_eq!(
+            directives.checksum,
+            Some(Checksum {
+                algorithm: Algorithm::Hash160,
+                len: 8,
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "checksum length out of range (expected 1 to 20)")]
+    fn parse_directives_checksum_len() {
+        parse_directives(quote!(@checksum(hash160, 21); OP_DUP));
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown lint \"foo\"")]
+    fn parse_directives_unknown_lint() {
+        parse_directives(quote!(@lint(foo); OP_CHECKSIG));
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown directive \"foo\"")]
+    fn parse_directives_unknown() {
+        parse_directives(quote!(@foo; OP_CHECKSIG));
+    }
+
+    #[test]
+    fn parse_callback_expression() {
+        let (callback, tokens) = parse_callback(quote!(|item| log(item); OP_CHECKSIG));
+        assert_eq!(callback.to_string(), quote!(|item| log(item)).to_string());
+        assert_eq!(tokens.to_string(), quote!(OP_CHECKSIG).to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "expected callback followed by ';'")]
+    fn parse_callback_missing() {
+        parse_callback(quote!(OP_CHECKSIG));
+    }
+
+    #[test]
+    fn parse_witness_program_prefix() {
+        let (program, _, tokens) = parse_witness_program(quote!(
+            0x4ae81572f06e1b88fd5ced7a1a000945432e83e1551e6f721ee9c00b8cc33260;
+            OP_PUSHNUM_1
+        ));
+
+        assert_eq!(program.len(), 32);
+        assert_eq!(program[0], 0x4a);
+        assert_eq!(tokens.to_string(), "OP_PUSHNUM_1");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a 32-byte witness program, found 2 bytes")]
+    fn parse_witness_program_length() {
+        parse_witness_program(quote!(0x0102; OP_PUSHNUM_1));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected witness program hex literal followed by ';'")]
+    fn parse_witness_program_missing() {
+        parse_witness_program(quote!(OP_PUSHNUM_1));
+    }
+
+    #[test]
+    fn parse_witness_template_items() {
+        let (template, tokens) =
+            parse_witness_template(quote!([_sig, 0x0102, key.to_bytes()]; OP_CHECKSIG));
+
+        assert_eq!(template.len(), 3);
+        assert!(template[0].to_string().contains("Placeholder"));
+        assert!(template[0].to_string().contains("\"sig\""));
+        assert!(template[1].to_string().contains("b\"\\x01\\x02\""));
+        assert!(template[2].to_string().contains("key . to_bytes ()"));
+        assert_eq!(tokens.to_string(), "OP_CHECKSIG");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected witness template in brackets followed by ';'")]
+    fn parse_witness_template_missing() {
+        parse_witness_template(quote!(OP_CHECKSIG));
+    }
+
+    #[test]
+    fn parse_const_name_visibility() {
+        let (visibility, name, tokens) = parse_const_name(quote!(pub(crate) FOO: OP_DUP));
+        assert_eq!(visibility.to_string(), quote!(pub(crate)).to_string());
+        assert_eq!(name, "FOO");
+        assert_eq!(tokens.to_string(), quote!(OP_DUP).to_string());
+
+        let (visibility, name, _) = parse_const_name(quote!(BAR: OP_DUP));
+        assert!(visibility.is_empty());
+        assert_eq!(name, "BAR");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected constant name followed by ':'")]
+    fn parse_const_name_missing() {
+        parse_const_name(quote!(OP_DUP OP_DROP));
+    }
+
+    #[test]
+    fn parse_guarded_condition() {
+        let tokens = parse_guarded(quote!(<cond> => { OP_DROP 1 }));
+        assert_eq!(
+            tokens.to_string(),
+            quote!(<cond> OP_IF OP_DROP 1 OP_ENDIF).to_string()
+        );
+
+        let tokens = parse_guarded(quote!({ OP_DROP }));
+        assert_eq!(
+            tokens.to_string(),
+            quote!(OP_IF OP_DROP OP_ENDIF).to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected '=>' between the condition and the script")]
+    fn parse_guarded_missing_arrow() {
+        parse_guarded(quote!(<cond> { OP_DROP }));
+    }
+
+    #[test]
+    fn parse_empty() {
+        assert!(parse(quote!()).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected token")]
+    fn parse_unexpected_token() {
+        parse(quote!(OP_CHECKSIG &));
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown opcode \"A\"")]
+    fn parse_invalid_opcode() {
+        parse(quote!(OP_CHECKSIG A B));
+    }
+
+    #[test]
+    fn parse_opcodes() {
+        let syntax = parse(quote!(OP_CHECKSIG OP_HASH160));
+
+        if let Syntax::Opcode(opcode) = syntax[0].0 {
+            assert_eq!(opcode, opcodes::OP_CHECKSIG);
+        } else {
+            panic!();
+        }
+
+        if let Syntax::Opcode(opcode) = syntax[1].0 {
+            assert_eq!(opcode, opcodes::OP_HASH160);
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "unterminated escape")]
+    fn parse_unterminated_escape() {
+        parse(quote!(OP_CHECKSIG < abc));
+    }
+
+    #[test]
+    fn parse_escape() {
+        let syntax = parse(quote!(OP_CHECKSIG<abc>));
+
+        if let Syntax::Escape(tokens) = &syntax[1].0 {
+            let tokens = tokens.clone().into_iter().collect::<Vec<TokenTree>>();
+
+            assert_eq!(tokens.len(), 1);
+            if let TokenTree::Ident(_) = tokens[0] {
+                assert_eq!(tokens[0].to_string(), "abc");
+            } else {
+                panic!()
+            }
+        } else {
+            panic!()
+        }
+    }
+
+    #[test]
+    fn parse_escape_generics() {
+        let syntax =
+            parse(quote!(<Vec::<u8>::new()> <x: Vec<Vec<u8>>> <a >> 2> <b << 1 >= c> OP_ADD));
+
+        let escapes: Vec<String> = syntax
+            .iter()
+            .filter_map(|(item, _)| match item {
+                Syntax::Escape(tokens) => Some(tokens.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            escapes,
+            vec![
+                "Vec :: < u8 > :: new ()",
+                "x : Vec < Vec < u8 >>",
+                "a >> 2",
+                "b << 1 >= c",
+            ]
+        );
+        assert_eq!(syntax.len(), 5);
+    }
+
+    #[test]
+    fn parse_escape_block() {
+        let syntax = parse(quote!(<{ if a > b { a } else { b } }> OP_ADD));
+
+        assert_eq!(syntax.len(), 2);
+        if let Syntax::Escape(tokens) = &syntax[0].0 {
+            assert_eq!(tokens.to_string(), "{ if a > b { a } else { b } }");
+        } else {
+            panic!()
+        }
+    }
+
+    #[test]
+    fn parse_dollar_escape() {
+        let syntax = parse(quote!(OP_HASH160 $pubkey_hash OP_EQUALVERIFY));
+
+        assert_eq!(syntax.len(), 3);
+        if let Syntax::Escape(tokens) = &syntax[1].0 {
+            assert_eq!(tokens.to_string(), "pubkey_hash");
+        } else {
+            panic!()
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "expected an identifier after `$`")]
+    fn parse_dollar_literal() {
+        parse(quote!($ 1));
+    }
+
+    #[test]
+    fn parse_brace_escape() {
+        let syntax = parse(quote!({ a < b } OP_ADD { @byte(5) }));
+
+        assert_eq!(syntax.len(), 3);
+        if let Syntax::Escape(tokens) = &syntax[0].0 {
+            assert_eq!(tokens.to_string(), "a < b");
+        } else {
+            panic!()
+        }
+        assert!(matches!(&syntax[2].0, Syntax::Bytes(bytes) if bytes == &[5]));
+    }
+
+    #[test]
+    #[should_panic(expected = "unterminated escape")]
+    fn parse_escape_comparison() {
+        parse(quote!(<a < b> OP_ADD));
+    }
+
+    #[test]
+    fn parse_byte_literal() {
+        let syntax = parse(quote!(<@byte(5)>));
+
+        if let Syntax::Bytes(bytes) = &syntax[0].0 {
+            assert_eq!(bytes, &vec![5]);
+        } else {
+            panic!()
+        }
+    }
+
+    #[test]
+    fn parse_builtin_name_without_prefix() {
+        let syntax = parse(quote!(<byte(5)> <eq(x)>));
+
+        assert_eq!(syntax.len(), 2);
+        assert!(matches!(&syntax[0].0, Syntax::Escape(escape) if escape.to_string() == "byte (5)"));
+        assert!(matches!(&syntax[1].0, Syntax::Escape(escape) if escape.to_string() == "eq (x)"));
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown builtin @bytes")]
+    fn parse_unknown_builtin() {
+        parse(quote!(<@bytes(5)>));
+    }
+
+    #[test]
+    fn parse_byte_literal_forms() {
+        let syntax =
+            parse(quote!(<@byte(0x05)> <@byte(5u8)> <@byte(0b101)> <@byte(0o5)> <@byte(0xff_u8)>));
+
+        assert_eq!(syntax.len(), 5);
+        for (item, expected) in syntax.iter().zip(&[5, 5, 5, 5, 0xff]) {
+            assert!(matches!(&item.0, Syntax::Bytes(bytes) if bytes == &[*expected]));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "byte value out of range (expected 0 to 255)")]
+    fn parse_byte_hex_out_of_range() {
+        parse(quote!(<@byte(0x100)>));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid integer literal for @byte")]
+    fn parse_byte_invalid_literal() {
+        parse(quote!(<@byte("5")>));
+    }
+
+    #[test]
+    fn parse_byte_expression() {
+        let syntax = parse(quote!(<@byte(version + 1)>));
+
+        if let Syntax::Byte(tokens) = &syntax[0].0 {
+            assert_eq!(tokens.to_string(), "version + 1");
+        } else {
+            panic!()
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "byte value out of range (expected 0 to 255)")]
+    fn parse_byte_out_of_range() {
+        parse(quote!(<@byte(256)>));
+    }
+
+    #[test]
+    fn parse_then() {
+        let syntax = parse(quote!(<@then(foo.bar(), [OP_DUP, OP_HASH160])>));
+
+        assert_eq!(syntax.len(), 3);
+        if let Syntax::Escape(tokens) = &syntax[0].0 {
+            assert_eq!(tokens.to_string(), "foo . bar ()");
+        } else {
+            panic!()
+        }
+        if let (Syntax::Opcode(first), Syntax::Opcode(second)) = (&syntax[1].0, &syntax[2].0) {
+            assert_eq!(*first, opcodes::OP_DUP);
+            assert_eq!(*second, opcodes::OP_HASH160);
+        } else {
+            panic!()
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "expected @then(value, [opcodes])")]
+    fn parse_then_missing_opcodes() {
+        parse(quote!(<@then(foo)>));
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown opcode \"OP_FOO\"")]
+    fn parse_then_unknown_opcode() {
+        parse(quote!(<@then(foo, [OP_FOO])>));
+    }
+
+    #[test]
+    fn parse_eq() {
+        let syntax = parse(quote!(<@eq(hash)> <@eqverify(@byte(5))>));
+
+        assert_eq!(syntax.len(), 4);
+        if let Syntax::Escape(escape) = &syntax[0].0 {
+            assert_eq!(escape.to_string(), quote!(hash).to_string());
+        } else {
+            panic!();
+        }
+        assert!(matches!(syntax[1].0, Syntax::Opcode(opcodes::OP_EQUAL)));
+        assert!(matches!(&syntax[2].0, Syntax::Bytes(bytes) if bytes == &[5]));
+        assert!(matches!(
+            syntax[3].0,
+            Syntax::Opcode(opcodes::OP_EQUALVERIFY)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a value to compare with")]
+    fn parse_eq_empty() {
+        parse(quote!(<@eq()>));
+    }
+
+    #[test]
+    fn parse_push_bytes() {
+        let syntax = parse(quote!(OP_PUSHBYTES_2 0x0102 OP_PUSHBYTES_1 OP_DUP));
+
+        assert_eq!(syntax.len(), 3);
+        assert!(matches!(&syntax[0].0, Syntax::Bytes(bytes) if bytes == &[1, 2]));
+        assert!(matches!(
+            syntax[1].0,
+            Syntax::Opcode(opcodes::OP_PUSHBYTES_1)
+        ));
+        assert!(matches!(syntax[2].0, Syntax::Opcode(opcodes::OP_DUP)));
+    }
+
+    #[test]
+    fn parse_push_bytes_without_hex() {
+        let syntax = parse(quote!(OP_PUSHBYTES_1 5 OP_PUSHBYTES_1 <x> OP_PUSHBYTES_1 0b00000101));
+
+        assert_eq!(syntax.len(), 6);
+        for i in [0, 2, 4] {
+            assert!(matches!(
+                syntax[i].0,
+                Syntax::Opcode(opcodes::OP_PUSHBYTES_1)
+            ));
+        }
+        assert!(matches!(syntax[1].0, Syntax::Int(5)));
+        assert!(matches!(syntax[3].0, Syntax::Escape(_)));
+        assert!(matches!(&syntax[5].0, Syntax::Bytes(bytes) if bytes == &[5]));
+    }
+
+    #[test]
+    #[should_panic(expected = "OP_PUSHBYTES_3 expects 3 bytes of data, found 2")]
+    fn parse_push_bytes_mismatch() {
+        parse(quote!(OP_PUSHBYTES_3 0x0102));
+    }
+
+    #[test]
+    fn parse_skip() {
+        let syntax = parse(quote!(OP_DUP #[skip] { OP_DROP <foo> 0x01 } OP_CHECKSIG));
+
+        assert_eq!(syntax.len(), 2);
+        assert!(matches!(syntax[0].0, Syntax::Opcode(opcodes::OP_DUP)));
+        assert!(matches!(syntax[1].0, Syntax::Opcode(opcodes::OP_CHECKSIG)));
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown opcode \"OP_FOO\"")]
+    fn parse_skip_invalid() {
+        parse(quote!(
+            #[skip]
+            {
+                OP_FOO
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_aliases() {
+        let syntax = parse(quote!(
+            OP_0 OP_FALSE OP_TRUE OP_NOP2 OP_CHECKLOCKTIMEVERIFY OP_NOP3 OP_CHECKSEQUENCEVERIFY
+        ));
+        let codes: Vec<u8> = syntax
+            .iter()
+            .map(|(item, _)| match item {
+                Syntax::Opcode(opcode) => opcode.to_u8(),
+                _ => panic!("expected opcode"),
+            })
+            .collect();
+
+        assert_eq!(codes, vec![0x00, 0x00, 0x51, 0xb1, 0xb1, 0xb2, 0xb2]);
+    }
+
+    #[test]
+    fn parse_opcode_names() {
+        let syntax = parse(quote!(OP_CHECKSIG checksig CheckSig CHECKSIG op_checksig));
+
+        assert_eq!(syntax.len(), 5);
+        for (item, _) in syntax {
+            assert!(matches!(item, Syntax::Opcode(opcodes::OP_CHECKSIG)));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown opcode \"checksgi\"")]
+    fn parse_opcode_names_unknown() {
+        parse(quote!(checksgi));
+    }
+
+    #[test]
+    fn parse_raw() {
+        let syntax = parse(quote!(raw 0x4c02 0xabcd));
+
+        assert_eq!(syntax.len(), 2);
+        assert!(matches!(&syntax[0].0, Syntax::Raw(bytes) if bytes == &[0x4c, 0x02]));
+        assert!(matches!(&syntax[1].0, Syntax::Bytes(bytes) if bytes == &[0xab, 0xcd]));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a hex literal after `raw`")]
+    fn parse_raw_invalid() {
+        parse(quote!(raw 1));
+    }
+
+    #[test]
+    fn parse_pushdata() {
+        let syntax = parse(quote!(pushdata1(0xab) pushdata2(0xab) pushdata4("a") pushdata1));
+
+        assert!(matches!(&syntax[0].0, Syntax::Raw(bytes) if bytes == &[0x4c, 1, 0xab]));
+        assert!(matches!(&syntax[1].0, Syntax::Raw(bytes) if bytes == &[0x4d, 1, 0, 0xab]));
+        assert!(matches!(&syntax[2].0, Syntax::Raw(bytes) if bytes == &[0x4e, 1, 0, 0, 0, b'a']));
+        assert!(matches!(syntax[3].0, Syntax::Opcode(opcodes::OP_PUSHDATA1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a hex or string literal")]
+    fn parse_pushdata_invalid() {
+        parse(quote!(pushdata1(1)));
+    }
+
+    #[test]
+    fn parse_pushnum() {
+        let syntax = parse(quote!(<@pushnum(255, 4)> <@pushnum(-1, 2)> <@pushnum(x, 4)>));
+
+        assert!(matches!(&syntax[0].0, Syntax::Bytes(bytes) if bytes == &[0xff, 0, 0, 0]));
+        assert!(matches!(&syntax[1].0, Syntax::Bytes(bytes) if bytes == &[0x01, 0x80]));
+        assert!(matches!(syntax[2].0, Syntax::Builtin { .. }));
+    }
+
+    #[test]
+    #[should_panic(expected = "128 doesn't fit in a 1-byte script number")]
+    fn parse_pushnum_overflow() {
+        parse(quote!(<@pushnum(128, 1)>));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid width for pushnum (expected 1 to 8)")]
+    fn parse_pushnum_width() {
+        parse(quote!(<@pushnum(1, 9)>));
+    }
+
+    #[test]
+    fn parse_le() {
+        let syntax = parse(quote!(<@le32(258 + 1)> <@le64(1)> <@le32(x)>));
+
+        assert!(matches!(&syntax[0].0, Syntax::Bytes(bytes) if bytes == &[0x03, 0x01, 0, 0]));
+        assert!(matches!(&syntax[1].0, Syntax::Bytes(bytes) if bytes == &[1, 0, 0, 0, 0, 0, 0, 0]));
+        assert!(matches!(syntax[2].0, Syntax::Builtin { .. }));
+    }
+
+    #[test]
+    #[should_panic(expected = "value out of range (expected an unsigned integer)")]
+    fn parse_le_negative() {
+        parse(quote!(<@le32(-1)>));
+    }
+
+    #[test]
+    fn parse_scriptnum() {
+        let syntax =
+            parse(quote!(<@scriptnum(5)> <@scriptnum(-255, 2)> <@scriptnum(0)> <@scriptnum(x, 5)>));
+
+        assert!(matches!(&syntax[0].0, Syntax::Bytes(bytes) if bytes == &[5]));
+        assert!(matches!(&syntax[1].0, Syntax::Bytes(bytes) if bytes == &[0xff, 0x80]));
+        assert!(matches!(&syntax[2].0, Syntax::Bytes(bytes) if bytes.is_empty()));
+        assert!(matches!(syntax[3].0, Syntax::Builtin { .. }));
+    }
+
+    #[test]
+    #[should_panic(expected = "255 doesn't fit in a 1-byte script number")]
+    fn parse_scriptnum_width() {
+        parse(quote!(<@scriptnum(255, 1)>));
+    }
+
+    #[test]
+    fn parse_scriptnum_suffixed_width() {
+        let syntax = parse(quote!(<@scriptnum(255, 2usize)> <@pushnum(255, 4usize)>));
+
+        assert!(matches!(&syntax[0].0, Syntax::Bytes(bytes) if bytes == &[0xff, 0]));
+        assert!(matches!(&syntax[1].0, Syntax::Bytes(bytes) if bytes == &[0xff, 0, 0, 0]));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid width for scriptnum (expected 1 to 8)")]
+    fn parse_scriptnum_zero_width() {
+        parse(quote!(<@scriptnum(0, 0)>));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid width for scriptnum (expected 1 to 8)")]
+    fn parse_scriptnum_wide() {
+        parse(quote!(<@scriptnum(x, 9)>));
+    }
+
+    #[test]
+    fn parse_unknown_opcode() {
+        let syntax = parse(quote!(OP_UNKNOWN(0xba) OP_UNKNOWN(187)));
+
+        assert!(matches!(&syntax[0].0, Syntax::Opcode(opcode) if opcode.to_u8() == 0xba));
+        assert!(matches!(&syntax[1].0, Syntax::Opcode(opcode) if opcode.to_u8() == 0xbb));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected an opcode byte")]
+    fn parse_unknown_opcode_invalid() {
+        parse(quote!(OP_UNKNOWN(0x1234)));
+    }
+
+    #[test]
+    fn parse_doc_comment() {
+        let syntax = parse(quote!(
+            OP_IF
+            /// timeout path
+            OP_DROP
+            OP_ENDIF
+        ));
+
+        assert_eq!(syntax.len(), 3);
+        assert!(matches!(syntax[1].0, Syntax::Opcode(opcodes::OP_DROP)));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected #[skip]")]
+    fn parse_unknown_attribute() {
+        parse(quote!(
+            #[foo]
+            {
+                OP_DUP
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_addr_literal() {
+        let syntax = parse(quote!(<@addr("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")>));
+
+        assert_eq!(syntax.len(), 2);
+        assert!(matches!(&syntax[0].0, Syntax::Bytes(bytes) if bytes.is_empty()));
+        assert!(matches!(&syntax[1].0, Syntax::Bytes(bytes) if bytes.len() == 20));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid address \"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5\"")]
+    fn parse_addr_invalid() {
+        parse(quote!(<@addr("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5")>));
+    }
+
+    #[test]
+    fn parse_instructions() {
+        let syntax = parse(quote!(<@instructions(bytes)>));
+
+        assert_eq!(syntax.len(), 1);
+        assert!(
+            matches!(&syntax[0].0, Syntax::Builtin { expression, .. } if expression.to_string().contains("Script :: from"))
+        );
+    }
+
+    #[test]
+    fn parse_redeem() {
+        let syntax = parse(quote!(<@redeem(script)>));
+
+        assert_eq!(syntax.len(), 1);
+        assert!(
+            matches!(&syntax[0].0, Syntax::Builtin { expression, .. } if expression.to_string().contains("as_bytes"))
+        );
+        assert!(
+            matches!(&syntax[0].0, Syntax::Builtin { source, .. } if source.to_string() == "@ redeem (script)")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid number literal (invalid digit found in string)")]
+    fn parse_invalid_int() {
+        parse(quote!(OP_CHECKSIG 12g34));
+    }
+
+    #[test]
+    fn parse_int() {
+        let syntax = parse(quote!(OP_CHECKSIG 1234));
+
+        if let Syntax::Int(n) = syntax[1].0 {
+            assert_eq!(n, 1234i64);
+        } else {
+            panic!()
+        }
+    }
+
+    #[test]
+    fn parse_int_separators() {
+        let syntax = parse(quote!(52_560 - 1_000_000));
+
+        assert!(matches!(syntax[0].0, Syntax::Int(52560)));
+        assert!(matches!(syntax[1].0, Syntax::Int(-1000000)));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected negative sign to be followed by number literal")]
+    fn parse_invalid_negative_sign() {
+        parse(quote!(OP_CHECKSIG - OP_HASH160));
+    }
+
+    #[test]
+    fn parse_negative_int() {
+        let syntax = parse(quote!(OP_CHECKSIG - 1234));
+
+        if let Syntax::Int(n) = syntax[1].0 {
+            assert_eq!(n, -1234i64);
+        } else {
+            panic!()
+        }
+    }
+
+    #[test]
+    fn parse_string() {
+        let syntax = parse(quote!(0 OP_IF "ord" 1 "text/plain" OP_ENDIF));
+
+        assert!(matches!(&syntax[2].0, Syntax::Bytes(bytes) if bytes == b"ord"));
+        assert!(matches!(&syntax[4].0, Syntax::Bytes(bytes) if bytes == b"text/plain"));
+    }
+
+    #[test]
+    fn parse_byte_string() {
+        let syntax = parse(quote!(b"\x01\x02abc"));
+
+        assert!(matches!(&syntax[0].0, Syntax::Bytes(bytes) if bytes == b"\x01\x02abc"));
+    }
+
+    #[test]
+    fn parse_binary() {
+        let syntax = parse(quote!(0b10110000 0b0000_0001_1111_1111));
+
+        assert!(matches!(&syntax[0].0, Syntax::Bytes(bytes) if bytes == &[0xb0]));
+        assert!(matches!(&syntax[1].0, Syntax::Bytes(bytes) if bytes == &[0x01, 0xff]));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid binary literal (expected a multiple of 8 binary digits)")]
+    fn parse_invalid_binary() {
+        parse(quote!(0b101));
+    }
+
+    #[test]
+    fn parse_hex_separators() {
+        let syntax = parse(quote!(0xdead_beef_0102));
+
+        if let Syntax::Bytes(bytes) = &syntax[0].0 {
+            assert_eq!(bytes, &vec![0xde, 0xad, 0xbe, 0xef, 0x01, 0x02]);
+        } else {
+            panic!()
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid hex literal (Odd number of digits)")]
+    fn parse_invalid_hex() {
+        parse(quote!(OP_CHECKSIG 0x123));
+    }
+
+    #[test]
+    fn parse_hex() {
+        let syntax = parse(quote!(OP_CHECKSIG 0x1234));
+
+        if let Syntax::Bytes(bytes) = &syntax[1].0 {
+            assert_eq!(bytes, &vec![0x12, 0x34]);
+        } else {
+            panic!()
+        }
+    }
+}
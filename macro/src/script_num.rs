@@ -0,0 +1,44 @@
+// Script number encoding, shared with the `bitcoin-script` crate (which
+// `include!`s this file) so numbers encoded while the macro is expanded match
+// the ones encoded at runtime.
+
+/// Encodes `value` as a minimal script number (CScriptNum). Zero is the empty
+/// byte array, and any `i64` fits in at most 9 bytes.
+pub fn minimal(value: i64) -> Vec<u8> {
+    let mut magnitude = value.unsigned_abs();
+    let mut bytes = Vec::with_capacity(9);
+    while magnitude > 0 {
+        bytes.push(magnitude as u8);
+        magnitude >>= 8;
+    }
+
+    // the top bit of the last byte is the sign, so a magnitude which uses it
+    // needs an extra byte
+    match bytes.last_mut() {
+        Some(last) if *last & 0x80 != 0 => bytes.push(if value < 0 { 0x80 } else { 0 }),
+        Some(last) if value < 0 => *last |= 0x80,
+        _ => {}
+    }
+    bytes
+}
+
+/// Encodes `value` as a script number of exactly `width` bytes, padding the
+/// magnitude with zero bytes before the sign bit. Returns `None` if the value
+/// doesn't fit, or if `width` is 0.
+pub fn padded(value: i64, width: usize) -> Option<Vec<u8>> {
+    let mut magnitude = value.unsigned_abs();
+    let mut bytes = vec![0; width];
+    for byte in bytes.iter_mut() {
+        *byte = magnitude as u8;
+        magnitude >>= 8;
+    }
+
+    let last = bytes.last_mut()?;
+    if magnitude != 0 || *last & 0x80 != 0 {
+        return None;
+    }
+    if value < 0 {
+        *last |= 0x80;
+    }
+    Some(bytes)
+}
@@ -0,0 +1,83 @@
+use super::parse::Syntax;
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::quote;
+
+/// Replaces the holes in a template (escapes containing only an identifier
+/// starting with `_`, optionally followed by `: Type`) with uses of closure
+/// parameters, returning the parameter list in order of first appearance.
+pub fn extract_holes(syntax: &mut [(Syntax, Span)]) -> Vec<TokenStream> {
+    let mut names: Vec<Ident> = Vec::new();
+    let mut params = Vec::new();
+
+    for (item, _) in syntax.iter_mut() {
+        let (name, ty) = match item {
+            Syntax::Escape(expression) => match parse_hole(expression) {
+                Some(hole) => hole,
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        *item = Syntax::Escape(quote!(#name));
+
+        if names.contains(&name) {
+            continue;
+        }
+        params.push(match ty {
+            Some(ty) => quote!(#name: #ty),
+            None => quote!(#name),
+        });
+        names.push(name);
+    }
+
+    params
+}
+
+fn parse_hole(expression: &TokenStream) -> Option<(Ident, Option<TokenStream>)> {
+    let mut tokens = expression.clone().into_iter();
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(name)) if name.to_string().starts_with('_') => name,
+        _ => return None,
+    };
+
+    match tokens.next() {
+        None => Some((name, None)),
+        Some(TokenTree::Punct(ref punct)) if punct.as_char() == ':' => {
+            Some((name, Some(tokens.collect())))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse;
+
+    #[test]
+    fn extract_holes_none() {
+        let mut syntax = parse(quote!(OP_DUP<foo>));
+        assert!(extract_holes(&mut syntax).is_empty());
+    }
+
+    #[test]
+    fn extract_holes_typed() {
+        let mut syntax = parse(quote!(<_a: PublicKey> OP_CHECKSIGVERIFY <_b> <_a> <_b + 1>));
+        let params = extract_holes(&mut syntax);
+
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].to_string(), "_a : PublicKey");
+        assert_eq!(params[1].to_string(), "_b");
+
+        if let Syntax::Escape(expression) = &syntax[3].0 {
+            assert_eq!(expression.to_string(), "_a");
+        } else {
+            panic!()
+        }
+        if let Syntax::Escape(expression) = &syntax[4].0 {
+            assert_eq!(expression.to_string(), "_b + 1");
+        } else {
+            panic!()
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use bitcoin::hashes::{hex::ToHex, sha256, Hash};
+
+/// Checks that a witness script commits to the given P2WSH witness program,
+/// i.e. that the program is the SHA256 hash of the script.
+pub fn check_program(program: &[u8], script: &[u8]) -> Result<(), String> {
+    let hash = sha256::Hash::hash(script);
+    if hash[..] == *program {
+        return Ok(());
+    }
+
+    Err(format!(
+        "witness script hashes to 0x{}, which does not match the expected witness program 0x{}",
+        hash.to_hex(),
+        program.to_hex()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROGRAM: [u8; 32] = [
+        0x4a, 0xe8, 0x15, 0x72, 0xf0, 0x6e, 0x1b, 0x88, 0xfd, 0x5c, 0xed, 0x7a, 0x1a, 0x00, 0x09,
+        0x45, 0x43, 0x2e, 0x83, 0xe1, 0x55, 0x1e, 0x6f, 0x72, 0x1e, 0xe9, 0xc0, 0x0b, 0x8c, 0xc3,
+        0x32, 0x60,
+    ];
+
+    #[test]
+    fn check_program_match() {
+        assert_eq!(check_program(&PROGRAM, &[0x51]), Ok(()));
+    }
+
+    #[test]
+    fn check_program_mismatch() {
+        let err = check_program(&PROGRAM, &[0x52]).unwrap_err();
+        assert!(err.starts_with("witness script hashes to 0x"));
+        assert!(err.ends_with(
+            "expected witness program 0x4ae81572f06e1b88fd5ced7a1a000945432e83e1551e6f721ee9c00b8cc33260"
+        ));
+    }
+}
@@ -0,0 +1,44 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// One item of a script, as returned by `bitcoin_script_ast!`.
+///
+/// These types are intended for external tooling such as linters and
+/// formatters. New kinds of items may be added, so match on
+/// [`ScriptItemKind`] with a wildcard arm.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScriptItem {
+    pub kind: ScriptItemKind,
+    /// Where the item was written in the macro invocation.
+    pub span: SourceSpan,
+}
+
+/// What a [`ScriptItem`] contributes to the script.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum ScriptItemKind {
+    /// An opcode written by name, e.g. `OP_CHECKSIG`.
+    Opcode { name: String, byte: u8 },
+    /// A hex or integer literal. `data` is the pushed element (the script
+    /// number encoding for integers) and `encoded` holds the bytes added to
+    /// the script, including the push opcode.
+    Push { data: Vec<u8>, encoded: Vec<u8> },
+    /// An escape, which is only evaluated at runtime. `source` is the Rust
+    /// expression as written.
+    Escape { source: String },
+    /// Bytes written with `raw`, which are added to the script as they are.
+    Raw { bytes: Vec<u8> },
+    /// The push appended by the `@checksum(algorithm, len);` directive, which
+    /// is placed at the macro invocation. `algorithm` is the name as written.
+    Checksum { algorithm: String, len: usize },
+}
+
+/// A position in the source file, with a one-based line and column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SourceSpan {
+    pub line: usize,
+    pub column: usize,
+}
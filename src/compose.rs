@@ -0,0 +1,55 @@
+use bitcoin::blockdata::opcodes::all::OP_CODESEPARATOR;
+use bitcoin::Script;
+
+/// Concatenates two scripts with an `OP_CODESEPARATOR` between them.
+///
+/// Signature checks in `b` then only sign the script from the separator on:
+/// in legacy and segwit v0 scripts the signed script code is everything after
+/// the last executed `OP_CODESEPARATOR` (so `a` is not committed to), and in
+/// tapscript the signature commits to the separator's position instead.
+/// Signature checks in `a` are unaffected.
+pub fn concat_with_separator(a: &Script, b: &Script) -> Script {
+    let mut bytes = Vec::with_capacity(a.len() + 1 + b.len());
+    bytes.extend_from_slice(a.as_bytes());
+    bytes.push(OP_CODESEPARATOR.to_u8());
+    bytes.extend_from_slice(b.as_bytes());
+    Script::from(bytes)
+}
+
+/// Builds `OP_SIZE <len> OP_EQUALVERIFY`, which fails the script unless the
+/// top stack item is `len` bytes long, e.g. to guard a hash preimage. `len`
+/// is pushed as a script integer. Interpolate the result into a larger
+/// script with `<size_check!(len)>`.
+///
+/// ```rust
+/// # #![feature(proc_macro_hygiene)]
+/// # use bitcoin_script::{bitcoin_script, size_check};
+/// let hash = vec![0; 32];
+/// let script = bitcoin_script!(<size_check!(32)> OP_SHA256 <hash> OP_EQUAL);
+/// ```
+#[macro_export]
+macro_rules! size_check {
+    ($len:expr) => {
+        $crate::bitcoin_script!(OP_SIZE <$len as i64> OP_EQUALVERIFY)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concat_separator() {
+        let a = Script::from(vec![0x76, 0xa9]);
+        let b = Script::from(vec![0x01, 0xab, 0xac]);
+
+        assert_eq!(
+            concat_with_separator(&a, &b).to_bytes(),
+            vec![0x76, 0xa9, 0xab, 0x01, 0xab, 0xac]
+        );
+        assert_eq!(
+            concat_with_separator(&Script::new(), &Script::new()).to_bytes(),
+            vec![0xab]
+        );
+    }
+}
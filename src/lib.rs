@@ -56,6 +56,16 @@
 //!
 //! All normal opcodes are available, in the form `OP_X`.
 //!
+//! Common alternative names are accepted as well, whichever names the `bitcoin` crate uses: `OP_0` and `OP_FALSE`, `OP_TRUE`, `OP_NOP2`, `OP_CLTV` and `OP_CHECKLOCKTIMEVERIFY`, and `OP_NOP3`, `OP_CSV` and `OP_CHECKSEQUENCEVERIFY`.
+//!
+//! Names are also accepted without the `OP_` prefix and in any case (e.g. `checksig`, `CheckSig` or `CHECKSIG`), as printed by other tools such as btcdeb or Bitcoin Core's `decodescript`.
+//!
+//! Opcodes which have no name (e.g. proposed opcodes or `OP_SUCCESSx`) can be written by their byte value, as in `OP_UNKNOWN(0xba)`.
+//!
+//! Bytes written as `raw 0x...` are added to the script as they are, without a push opcode, e.g. for a pre-encoded script fragment or a hand-crafted push (`raw 0x4c0100` is `OP_PUSHDATA1` pushing one zero byte).
+//!
+//! Data is normally pushed with the shortest encoding, but `pushdata1(...)`, `pushdata2(...)` and `pushdata4(...)` push a hex or string literal with that opcode instead (e.g. `pushdata2(0x0102)`), for protocols and tests which need a specific framing.
+//!
 //! ```rust
 //! # #![feature(proc_macro_hygiene)]
 //! # use bitcoin_script::bitcoin_script;
@@ -64,7 +74,7 @@
 //!
 //! #### Integer Literals
 //!
-//! Positive and negative 64-bit integer literals can be used, and will resolve to their most efficient encoding.
+//! Positive and negative 64-bit integer literals can be used, and will resolve to their most efficient encoding. As in Rust, digits can be separated with underscores for readability (e.g. `52_560`).
 //!
 //! For example:
 //! -`2` will resolve to `OP_PUSHNUM_2` (`0x52`)
@@ -73,12 +83,12 @@
 //! ```rust
 //! # #![feature(proc_macro_hygiene)]
 //! # use bitcoin_script::bitcoin_script;
-//! let script = bitcoin_script!(123 -456 999999);
+//! let script = bitcoin_script!(123 -456 999_999);
 //! ```
 //!
 //! #### Hex Literals
 //!
-//! Hex strings can be specified, prefixed with `0x`.
+//! Hex strings can be specified, prefixed with `0x`. Underscores can be used to group the digits of long strings (e.g. `0xdead_beef`), and are ignored.
 //!
 //! ```rust
 //! # #![feature(proc_macro_hygiene)]
@@ -88,15 +98,145 @@
 //! );
 //! ```
 //!
+//! A hex literal directly after `OP_PUSHBYTES_N` (for `N` from 1 to 75) is
+//! pushed with that opcode, and must be exactly `N` bytes long. Since direct
+//! pushes are already used for data of up to 75 bytes, this doesn't change
+//! the encoding, but makes the framing explicit and checked.
+//!
+//! Note that this changes the meaning of existing scripts which follow
+//! `OP_PUSHBYTES_N` with a hex literal: the literal used to be pushed
+//! separately, with its own push opcode. Anything else after the opcode (an
+//! integer, a binary literal or an escape) still leaves it as a lone opcode,
+//! and a lone opcode followed by a hex literal can be written with `raw`,
+//! e.g. `raw 0x02 0x0102`.
+//!
+//! #### Binary Literals
+//!
+//! Binary strings prefixed with `0b` are pushed as bytes in the same way, 8
+//! digits per byte, e.g. for bit flags. They must have a multiple of 8
+//! digits, and can be grouped with underscores.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::bitcoin_script;
+//! let script = bitcoin_script!(0b0100_0000 OP_AND);
+//! assert_eq!(script.to_bytes(), vec![0x01, 0x40, 0x84]);
+//! ```
+//!
+//! #### String Literals
+//!
+//! String literals (including escapes and raw strings) are pushed as their
+//! UTF-8 bytes, e.g. for the content type of an inscription envelope.
+//! Byte strings (e.g. `b"\x01\x02abc"`) are pushed as their bytes, which is
+//! convenient for data mixing ASCII text and binary.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::bitcoin_script;
+//! let script = bitcoin_script!(0 OP_IF "ord" 1 "text/plain;charset=utf-8" OP_ENDIF);
+//! ```
+//!
 //! #### Escape Sequences
 //!
 //! Dynamic Rust expressions are supported inside the script, surrounded by angle brackets. In many cases, this will just be a variable identifier, but this can also be a function call or arithmetic.
 //!
+//! The escape ends at the matching `>`, so it can contain generics (e.g. `<Vec::<u8>::new()>`) and shifts (e.g. `<x >> 8>`). A lone `<` is taken to open generic arguments, so comparisons have to be wrapped in parentheses, as in `<(a < b)>`, or in a block, as in `<{ if a > b { a } else { b } }>`. The contents of a block are taken as they are, so any expression can be written there.
+//!
+//! Escapes can also be delimited by braces instead of angle brackets, as in `{ expr }` (e.g. `{ a < b } OP_VERIFY`), which behaves the same as `<expr>`, including for builtins and template holes. Since the braces are a single token tree, everything inside them is taken as the expression.
+//!
+//! A single variable can also be interpolated as `$name`, shorthand for `<name>` (e.g. `OP_HASH160 $pubkey_hash OP_EQUALVERIFY`). Inside the body of a `macro_rules!` macro, `$name` is substituted first if the macro has a metavariable of that name.
+//!
 //! Rust expressions of the following types are supported:
 //!
-//! - `i64`
-//! - `Vec<u8>`
+//! - `i64`, and the smaller integer types (`u8`, `u16`, `u32`, `i8`, `i16`
+//!   and `i32`), pushed the same way
+//! - `u64` and `usize`, which panic rather than wrap if the value is larger
+//!   than the 5-byte script numbers read by `OP_CLTV` and `OP_CSV` can hold
+//!   (`2^39 - 1`)
+//! - `bitcoin::Amount`, pushed as its value in satoshis with the same range
+//!   check as `u64` (e.g. for covenant scripts comparing amounts)
+//! - `bool`, pushed as `OP_TRUE` or `OP_FALSE` (an empty array)
+//! - `Vec<u8>`, `&[u8]`, and byte arrays (`[u8; N]` or `&[u8; N]`), e.g.
+//!   hash digests and other fixed-size buffers. These are always pushed as
+//!   data, even when they are a single byte from 1 to 16; wrap them in
+//!   [`Minimal`] (e.g. `<Minimal(&bytes)>`) to push those as `OP_1` to
+//!   `OP_16` instead, as MINIMALDATA requires
+//! - `&str` and `String`, pushed as their UTF-8 bytes
 //! - [`bitcoin::PublicKey`](https://docs.rs/bitcoin/0.23.0/bitcoin/util/key/struct.PublicKey.html)
+//! - `bitcoin::secp256k1::PublicKey`, pushed as its 33-byte compressed
+//!   serialization
+//! - `bitcoin::XOnlyPublicKey`, pushed as its 32-byte serialization for
+//!   tapscript
+//! - `bitcoin::PubkeyHash`, `bitcoin::ScriptHash`, `bitcoin::WPubkeyHash` and
+//!   `bitcoin::WScriptHash`, pushed as their hash bytes (e.g.
+//!   `OP_HASH160 <pubkey.pubkey_hash()> OP_EQUALVERIFY`)
+//! - The `sha256`, `sha256d`, `ripemd160` and `hash160` hashes from
+//!   `bitcoin::hashes`, pushed as their bytes in internal order (which for
+//!   `sha256d` is the reverse of its hex display)
+//! - `bitcoin::Txid` and `bitcoin::Wtxid`, pushed as their 32 bytes in
+//!   internal order (the reverse of how they are displayed), as they appear
+//!   in transactions and in the serialized `OutPoint` below
+//! - `TapLeafHash`, `TapBranchHash` (the node hash of a taproot tree) and
+//!   `TapTweakHash` from `bitcoin::util::taproot`, pushed as their 32 bytes
+//! - [`ScriptNum`], pushed as a script number data element even for `-1` and
+//!   `1` to `16` (see its docs for how this differs from pushing an `i64`)
+//! - `bitcoin::util::taproot::ControlBlock`, pushed as its full serialization
+//!   (leaf version and output key parity byte, internal key, then the merkle
+//!   path), as it appears in a script-path spend's witness
+//! - `bitcoin::OutPoint` and `bitcoin::TxOut` (with the `introspection` feature),
+//!   pushed as their consensus serialization: an outpoint is the 32-byte txid in
+//!   internal byte order followed by the 4-byte little-endian output index, and an
+//!   output is the 8-byte little-endian value followed by the varint-prefixed
+//!   scriptPubKey
+//! - `Serialized` (with the `introspection` feature) wrapping any
+//!   reference to a `bitcoin::consensus::Encodable` value, e.g.
+//!   `<Serialized(&tx)>`, pushed as its consensus serialization for covenant
+//!   scripts
+//! - `bitcoin::blockdata::opcodes::All`, appended as the opcode itself
+//!   rather than pushed as data, for opcodes chosen at runtime
+//! - `bitcoin::blockdata::script::Instruction`, appended as the opcode or
+//!   push it was parsed from (with pushes re-encoded minimally). Streams of
+//!   instructions can be spliced in with [`Each`] (e.g.
+//!   `<Each(&instructions)>`), collecting them into a `Vec` first if the
+//!   iterator isn't `Clone`.
+//! - `bitcoin::Script` (or a reference to one), whose bytes are appended to
+//!   the script verbatim rather than pushed as data, for composing scripts
+//!   out of fragments. To push a script as a single data element instead
+//!   (e.g. a P2SH redeem script), use the `@redeem` builtin.
+//! - `bitcoin::EcdsaSig`, or a `(secp256k1::ecdsa::Signature,
+//!   EcdsaSighashType)` pair, pushed as the DER signature followed by the
+//!   sighash type byte as a scriptSig or witness expects
+//! - `bitcoin::SchnorrSig`, pushed as its 64 bytes, or 65 with the sighash
+//!   type byte when it isn't `SIGHASH_DEFAULT`, for tapscript witnesses
+//! - `bitcoin::Sequence`, pushed as a script number (e.g. before
+//!   `OP_CSV`). `OP_CSV` reads numbers of up to 5 bytes, so every sequence
+//!   value can be represented.
+//! - [`RelativeLockTime`], standing in for the relative lock time type this
+//!   version of `bitcoin` lacks, e.g. `<RelativeLockTime::Blocks(144)>
+//!   OP_CSV`. It is pushed as its sequence value, with the type flag set for
+//!   `RelativeLockTime::Time`.
+//! - `bitcoin::LockTime` and `bitcoin::PackedLockTime`, pushed as the
+//!   script number of their consensus value (a block height, or a UNIX
+//!   timestamp) for `OP_CLTV`
+//! - `Range<i64>`, pushing `start` and then `end` as two integers (e.g. the
+//!   bounds for `OP_WITHIN`)
+//! - [`Each`] wrapping a collection or iterator of any of these types (e.g.
+//!   `<Each(&keys)>`), pushing each item in order. This is how byte vectors
+//!   (`<Each(&preimages)>` for a witness-like stack) and signatures
+//!   (`<Each(&sigs)>`) are pushed as separate elements. A plain `Vec<T>` is
+//!   not supported, since a `Vec<u8>` is pushed as a single data element.
+//! - `()`, which pushes nothing (e.g. for an `if` without an `else`)
+//! - Tuples of up to 6 of any of these types (e.g. `<(&sig, &pubkey)>`),
+//!   pushing each element in order
+//! - [`ScriptFragment`] wrapping a function `Fn(Builder) -> Builder`, whose
+//!   additions to the builder (any number of opcodes and pushes) are appended
+//!   in place of the escape
+//! - References to any of these types
+//! - `Option<T>` of any of these types, pushing the value if it is `Some`
+//!   and nothing otherwise
+//! - `BTreeSet<T>` of any of these types, pushing each item in the set's
+//!   sorted order. `HashSet` is deliberately not supported since its
+//!   iteration order (and so the script) would differ between runs.
 //!
 //! ```rust
 //! # #![feature(proc_macro_hygiene)]
@@ -109,21 +249,453 @@
 //!     <2016 * 5> OP_CSV
 //! };
 //! ```
+//!
+//! When the type of an escape can't be inferred, e.g. for an integer literal
+//! which could be any of the integer types, it can be given with `<expr:
+//! Type>` (as in `<1 << 20: i64>`), which coerces the expression to `Type`
+//! before pushing it.
+//!
+//! Other types can be interpolated by implementing the [`Pushable`] trait,
+//! whose `bitcoin_script_push(&self, builder: Builder) -> Builder` method
+//! appends the value to a `bitcoin::blockdata::script::Builder`.
+//!
+//! Secret keys (`bitcoin::PrivateKey`, `secp256k1::SecretKey`,
+//! `secp256k1::KeyPair` and `ExtendedPrivKey`) are deliberately rejected with
+//! a compile error explaining that they must not be pushed, since scripts are
+//! published on-chain. Push the corresponding public key instead.
+//!
+//! Structs can also be made interpolatable with `#[derive(Pushable)]`, which
+//! pushes each field in declaration order (for tuple structs, in index
+//! order). Every field must itself be one of the supported types or another
+//! [`Pushable`] struct, and generic structs aren't supported.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::{bitcoin_script, Pushable};
+//! #[derive(Pushable)]
+//! struct HashLock {
+//!     hash: Vec<u8>,
+//!     timeout: i64,
+//! }
+//!
+//! let lock = HashLock { hash: vec![0xab; 32], timeout: 144 };
+//! let script = bitcoin_script!(OP_SHA256 <lock> OP_CSV);
+//! ```
+//!
+//! #### Big-Endian Integers
+//!
+//! Starting a script with the `@be;` directive makes integer literals and
+//! integer escapes which don't fit in a single opcode (i.e. outside of `-1` to
+//! `16`) push as 8-byte big-endian two's complement data instead of
+//! little-endian minimally-encoded script numbers. The result is not a valid
+//! script number for Bitcoin's arithmetic opcodes, so this is only intended
+//! for specialized chains and protocols which expect big-endian integers.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::bitcoin_script;
+//! let script = bitcoin_script!(@be; 1000 5);
+//! assert_eq!(script.to_bytes(), vec![8, 0, 0, 0, 0, 0, 0, 0x03, 0xe8, 0x55]);
+//! ```
+//!
+//! #### Checksums
+//!
+//! Starting a script with `@checksum(algorithm, len);` appends a final push
+//! of the first `len` bytes of a hash of the script bytes before it. The
+//! algorithm is one of `sha256`, `sha256d` (double SHA256, as used for
+//! base58check checksums) or `hash160` (RIPEMD160 of SHA256), and the hash
+//! bytes are taken in their internal order, i.e. as returned by the hash
+//! function rather than reversed for display. The checksum is computed while
+//! the macro is expanded if the script has no escapes, and when the script is
+//! built otherwise.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::bitcoin_script;
+//! let script = bitcoin_script!(@checksum(sha256d, 4); OP_RETURN 0x01020304);
+//! assert_eq!(script.len(), 11);
+//! ```
+//!
+//! #### Builtins
+//!
+//! Escapes of the form `<@name(args)>` are handled by the macro itself rather
+//! than pushing the value of a Rust expression. The `@` marks the names below
+//! as builtins, so a Rust function which shares one of their names can still
+//! be called as usual (`<byte(5)>` pushes whatever `byte(5)` returns), and an
+//! `@` followed by any other name is a compile error:
+//!
+//! - `@byte(n)` pushes `n` as a single-byte data element, failing if it does
+//!   not fit in a `u8` (at compile time for literals, otherwise at runtime).
+//!   Unlike a plain integer push, small values are not turned into
+//!   `OP_PUSHNUM_N` opcodes, so `<@byte(5)>` pushes `0x0105` rather than
+//!   `OP_PUSHNUM_5`.
+//! - `@then(value, [OP_X, ...])` pushes `value` (as any other escape would),
+//!   followed by the listed opcodes.
+//! - `@addr("...")` appends the scriptPubKey of an address (not as a push). A
+//!   string literal is parsed at compile time, so a malformed address is a
+//!   compile error; any other expression is parsed at runtime, panicking if it
+//!   is invalid.
+//! - `@instructions(bytes)` appends `bytes` (anything which is
+//!   `AsRef<[u8]>`) to the script as raw instructions. Interpolating the same
+//!   bytes directly would push them as a single data element instead, framed
+//!   with a push opcode.
+//! - `@redeem(script)` (or equivalently `@push_script(script)`) pushes the
+//!   serialization of a `bitcoin::Script` as a single data element, as a
+//!   P2SH scriptSig does with its redeem script. Interpolating the script
+//!   directly (`<script>`) appends its instructions instead.
+//! - `@eq(value)` and `@eqverify(value)` push `value`, followed by `OP_EQUAL`
+//!   or `OP_EQUALVERIFY` respectively, e.g. `OP_SHA256 <@eqverify(hash)>` in
+//!   a hash lock.
+//! - `@pushnum(value, width)` pushes `value` as a script number of exactly
+//!   `width` bytes (1 to 8), padded with zeros before the sign bit, e.g.
+//!   `<@pushnum(255, 4)>` pushes `0xff000000`, for gadgets which need
+//!   fixed-width stack elements. A value which doesn't fit fails at compile
+//!   time for constants, and panics at runtime otherwise.
+//! - `@le32(value)` and `@le64(value)` push `value` (a `u32` or `u64`) as 4 or 8
+//!   little-endian bytes rather than as a script number, as used in CTV
+//!   hashes and transaction introspection, e.g. `<@le32(version)>`.
+//! - `@scriptnum(value)` pushes an `i64` as a minimal script number data
+//!   element, as [`ScriptNum`] does, so small values aren't turned into
+//!   opcodes and values of more than 4 bytes are encoded the same way, for
+//!   data which a script will later read as a number. `@scriptnum(value,
+//!   width)` also fails if the encoding takes more than `width` bytes (1 to
+//!   8), at compile time for constants and otherwise at runtime.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::bitcoin_script;
+//! let script = bitcoin_script!(<@byte(5)> OP_EQUAL);
+//! assert_eq!(script.to_bytes(), vec![0x01, 0x05, 0x87]);
+//! ```
+//!
+//! #### Guarded Branches
+//!
+//! `guarded!(condition => { ... })` wraps a script in `OP_IF`/`OP_ENDIF`,
+//! preceded by the condition (usually an escape). If the condition is already
+//! on the stack, it can be left out along with the `=>`.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::guarded;
+//! let enabled: i64 = 1;
+//! let script = guarded!(<enabled> => { OP_DROP });
+//! assert_eq!(script.to_bytes(), vec![0x51, 0x63, 0x75, 0x68]);
+//! ```
+//!
+//! #### Script Templates
+//!
+//! `script_template!` takes the same syntax, but escapes containing only an
+//! identifier starting with `_` are holes to be filled in later. The macro
+//! returns a closure taking one argument per hole (in order of first
+//! appearance), optionally typed with `<_name: Type>`.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::script_template;
+//! let p2pk = script_template!(<_pubkey: bitcoin::PublicKey> OP_CHECKSIG);
+//! ```
+//!
+//! #### Comments
+//!
+//! Comments (`// ...` and `/* ... */`) can be written anywhere between items
+//! of the script, e.g. to annotate its branches. Doc comments (`/// ...`) are
+//! accepted as well and are ignored the same way.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::bitcoin_script;
+//! let script = bitcoin_script!(
+//!     OP_IF
+//!         // success path
+//!         OP_CHECKSIG
+//!     OP_ELSE
+//!         /// timeout path
+//!         144 OP_CSV OP_DROP /* relative lock */ OP_CHECKSIG
+//!     OP_ENDIF
+//! );
+//! ```
+//!
+//! #### Skipping
+//!
+//! A brace group marked with `#[skip]` is parsed, so its opcodes and
+//! literals must still be valid, but is left out of the generated script.
+//! Escapes inside of it are not evaluated, so they aren't type-checked either.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::bitcoin_script;
+//! let script = bitcoin_script!(OP_DUP #[skip] { OP_HASH160 } OP_DROP);
+//! assert_eq!(script.to_bytes(), vec![0x76, 0x75]);
+//! ```
+//!
+//! #### Compile-Time Checks
+//!
+//! Some mistakes are always reported as compile errors. In a bare multisig
+//! script (`m <key>... n OP_CHECKMULTISIG`, with `m` and `n` written as
+//! integers or `OP_PUSHNUM_N`), the key count `n` must match the number of
+//! 33- or 65-byte keys pushed between it and the threshold. Scripts which
+//! push the keys or counts from escapes are not checked.
+//!
+//! #### Lints
+//!
+//! Opt-in heuristic lints can be enabled per invocation with a leading
+//! `@lint(...);` directive. Lints emit compiler warnings and never change the
+//! generated script.
+//!
+//! - `unterminated`: warns when the script ends on an opcode which consumes
+//!   the top of the stack without leaving a result (e.g. `OP_DROP` or
+//!   `OP_EQUALVERIFY`)
+//! - `stack_limit`: warns when the pushes in the script could grow the stack
+//!   past the 1000-element consensus limit. Only scripts made up of opcodes
+//!   with a fixed stack effect are checked; escapes, branches and opcodes like
+//!   `OP_PICK` end the analysis.
+//! - `redundant_verify`: warns about `OP_VERIFY` following an opcode with a
+//!   combined `VERIFY` form (e.g. `OP_EQUAL OP_VERIFY`, which could be the
+//!   one-byte-shorter `OP_EQUALVERIFY`), or following an opcode which already
+//!   verifies its result, where the `OP_VERIFY` consumes a different stack
+//!   item and may be unintended.
+//! - `empty_branch`: warns when a branch of an `OP_IF` or `OP_NOTIF`
+//!   conditional (before `OP_ELSE`, or between `OP_ELSE` and `OP_ENDIF`)
+//!   contains nothing. The warning points at the opening `OP_IF`.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::bitcoin_script;
+//! let script = bitcoin_script! {
+//!     @lint(unterminated);
+//!     OP_DUP OP_HASH160 0x0102 OP_EQUALVERIFY OP_CHECKSIG
+//! };
+//! ```
+//!
+//! ### Fallible Escapes
+//!
+//! `try_bitcoin_script!` takes the same syntax but evaluates to a
+//! `Result<Script, E>`, and escapes can use `?` to bail out of building the
+//! script. Escapes are evaluated inside a closure, so `?` returns from the
+//! macro rather than from the enclosing function. The error type `E` is
+//! inferred, so it must be known from context (e.g. by returning the result
+//! directly or annotating the binding), and each error propagated with `?`
+//! must convert into it with `From`.
+//!
+//! Escapes can also evaluate to a `Result` of a pushable value without
+//! using `?`, in which case the value is pushed if it is `Ok` and the error
+//! is converted and returned otherwise (the error must be `Clone`, since
+//! escapes only borrow their value).
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::try_bitcoin_script;
+//! use std::str::FromStr;
+//! use bitcoin::{util::key::Error, PublicKey, Script};
+//!
+//! fn p2pk(key: &str) -> Result<Script, Error> {
+//!     try_bitcoin_script!(<PublicKey::from_str(key)?> OP_CHECKSIG)
+//! }
+//!
+//! assert!(p2pk("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798").is_ok());
+//! assert!(p2pk("not a key").is_err());
+//! ```
+//!
+//! ### Large Scripts
+//!
+//! Opcodes and literals are encoded while the macro is expanded, so a run of
+//! static items compiles down to a single byte string and only escapes
+//! generate code which runs when the script is built. Escapes which only do
+//! arithmetic on integer literals (e.g. `<2016 * 5>`) are evaluated while
+//! the macro is expanded too, and encoded like literals. Parsing and generation
+//! are linear in the number of tokens, and however many opcodes a static
+//! script has (BitVM-style constructions use tens of thousands), it expands to
+//! a single byte string rather than to code for each opcode.
+//!
+//! With the `fragments` feature enabled, `bitcoin_script_fragments!` takes the
+//! same input but evaluates to `(Script, Vec<Range<usize>>)`, where each range
+//! covers the bytes produced by one escape. This makes it possible to map
+//! instructions in a large composed script back to the fragment that emitted
+//! them.
+//!
+//! ### Constants
+//!
+//! Scripts without escapes can also be defined as constants with
+//! `bitcoin_script_const!`, in item position. Along with the script bytes it
+//! defines `_SIZE` and `_OPCODES` constants holding the script's length and
+//! its number of non-push opcodes (as counted against the 201 opcode limit),
+//! so build scripts and tests can check them at compile time.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::bitcoin_script_const;
+//! bitcoin_script_const!(pub HASH_LOCK: OP_SHA256 0x0102030405060708 OP_EQUAL);
+//!
+//! assert_eq!(HASH_LOCK_SIZE, 11);
+//! assert_eq!(HASH_LOCK_OPCODES, 2);
+//! let script = bitcoin::Script::from(HASH_LOCK.to_vec());
+//! ```
+//!
+//! ### Tracing
+//!
+//! For debugging, `bitcoin_script_traced!` takes a callback followed by `;`
+//! before the script, and calls it with the encoded bytes of each opcode or
+//! push as the script is built. Items are appended one at a time, so it is
+//! slower than `bitcoin_script!` and only intended for development.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::bitcoin_script_traced;
+//! let script = bitcoin_script_traced! {
+//!     |item: &[u8]| println!("{:x?}", item);
+//!     OP_HASH160 <vec![1, 2, 3]> OP_EQUAL
+//! };
+//! ```
+//!
+//! ### Reversed Scripts
+//!
+//! **Experimental:** [`bitcoin_script_rev!`] builds the script with its items
+//! in reverse order, as a tool for witness-stack ordering experiments and
+//! testing. Reversed scripts are generally not valid (e.g. `OP_IF` and
+//! `OP_ENDIF` swap places), so it shouldn't be used to build real scripts.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::{bitcoin_script, bitcoin_script_rev};
+//! assert_eq!(bitcoin_script_rev!(1 2 3), bitcoin_script!(3 2 1));
+//! ```
+//!
+//! ### Tooling
+//!
+//! `bitcoin_script_ast!` takes the same syntax but evaluates to a
+//! `Vec<`[`ScriptItem`]`>` instead of a script, describing each item with its
+//! resolved encoding and source location, for external linters and
+//! formatters. Escapes are recorded as their source text and never evaluated,
+//! as are builtins which are evaluated at runtime. Directives are applied, so
+//! `@be;` changes the encoding of integers and `@checksum` adds a final item.
+//! With the `serde` feature enabled, the AST types can be serialized.
+//!
+//! ### Including Files
+//!
+//! Large scripts can be kept in their own file, written in the same syntax,
+//! and included at compile time with `bitcoin_script_include!`. The path is
+//! resolved relative to the file containing the invocation. Escapes are not
+//! supported in included files.
+//!
+//! ```rust,ignore
+//! let script = bitcoin_script_include!("scripts/p2pkh.script");
+//! ```
+//!
+//! ### Validation
+//!
+//! [`validate_roundtrip`] checks that a script decodes cleanly through
+//! rust-bitcoin's instruction iterator, catching truncated pushes in scripts
+//! that were assembled by hand or received from elsewhere.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::{bitcoin_script, validate_roundtrip};
+//! let script = bitcoin_script!(OP_DUP OP_HASH160 0x0102030405 OP_EQUALVERIFY);
+//! assert!(validate_roundtrip(&script).is_ok());
+//! ```
+//!
+//! `assert_witness_program!` ties a witness script to its P2WSH commitment.
+//! It takes the expected 32-byte witness program as a hex literal followed by
+//! `;`, then a script without escapes, and fails to compile unless the SHA256
+//! hash of the script matches the program. It evaluates to the script.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::assert_witness_program;
+//! let script = assert_witness_program!(
+//!     0x4ae81572f06e1b88fd5ced7a1a000945432e83e1551e6f721ee9c00b8cc33260;
+//!     OP_PUSHNUM_1
+//! );
+//! ```
+//!
+//! ### Witness Templates
+//!
+//! `script_with_witness_template!` builds a locking script together with a
+//! skeleton of the witness stack which spends it, e.g. for test fixtures. The
+//! witness items come first, in brackets and followed by `;`. An item written
+//! as `_name` becomes a [`WitnessItem::Placeholder`] (for values like
+//! signatures which only exist at spending time), and any other item (a hex
+//! literal or an expression evaluating to bytes) becomes a
+//! [`WitnessItem::Bytes`]. The macro evaluates to `(Script, Vec<WitnessItem>)`.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::{script_with_witness_template, WitnessItem};
+//! let (script, witness) = script_with_witness_template! {
+//!     [_sig, 0x0102];
+//!     OP_SHA256 0x0102 OP_EQUALVERIFY OP_CHECKSIG
+//! };
+//! assert_eq!(witness[0], WitnessItem::Placeholder("sig".to_string()));
+//! ```
+//!
+//! ### Composing Scripts
+//!
+//! Interpolating a `Script` appends it verbatim. [`concat_with_separator`]
+//! joins two scripts with an `OP_CODESEPARATOR` in between, so signatures
+//! checked in the second script don't commit to the first.
+//!
+//! `size_check!(len)` builds the common `OP_SIZE <len> OP_EQUALVERIFY` guard,
+//! which checks that the top stack item (e.g. a hash preimage) is `len` bytes
+//! long, for interpolating as `<size_check!(len)>`.
+//!
+//! ```rust
+//! # #![feature(proc_macro_hygiene)]
+//! # use bitcoin_script::{bitcoin_script, size_check};
+//! let hash = vec![0; 32];
+//! let script = bitcoin_script!(<size_check!(32)> OP_SHA256 <hash> OP_EQUAL);
+//! ```
+//!
+//! ### Multisig
+//!
+//! `multisig_checked!(threshold, key, ...)` (or the [`checked_multisig`]
+//! function, for a slice of keys) assembles a bare multisig script, returning
+//! a [`MultisigError`] if a key is repeated or the threshold is out of range.
+//! `multisig_bip67!` (or [`bip67_multisig`]) additionally sorts the keys by
+//! their serialization first, as specified by BIP67.
+//!
+//! ### Nested SegWit
+//!
+//! `p2sh_p2wsh_script!` takes a witness script in the usual syntax and
+//! evaluates to a [`P2shP2wsh`] holding the three scripts of a P2SH-P2WSH
+//! output: the P2SH `script_pubkey`, the `redeem_script` pushed in the
+//! scriptSig (the P2WSH witness program), and the inner `witness_script`.
+//!
+//! ### Witness Programs
+//!
+//! A [`WitnessProgram`] pairs a segwit version with a program whose length
+//! has been checked for it (20 or 32 bytes for version 0, and 32 bytes for
+//! version 1). Pushing it pushes just the program, and its version can be
+//! pushed as the matching opcode, so an output script can be written as
+//! `0 <program>` or `<program.version()> <program>`.
 
-#![feature(proc_macro_hygiene)]
-
-mod generate;
-mod parse;
-
-use generate::generate;
-use parse::parse;
-use proc_macro::TokenStream;
-use proc_macro_error::{proc_macro_error, set_dummy};
-use quote::quote;
+mod ast;
+mod compose;
+mod lock_time;
+mod multisig;
+mod nested;
+mod program;
+mod pushable;
+mod script_num;
+mod validate;
+mod witness;
 
-#[proc_macro]
-#[proc_macro_error]
-pub fn bitcoin_script(tokens: TokenStream) -> TokenStream {
-    set_dummy(quote!((::bitcoin::Script::new())));
-    generate(parse(tokens.into())).into()
-}
+pub use ast::{ScriptItem, ScriptItemKind, SourceSpan};
+#[cfg(feature = "fragments")]
+pub use bitcoin_script_macro::bitcoin_script_fragments;
+pub use bitcoin_script_macro::Pushable;
+pub use bitcoin_script_macro::{
+    assert_witness_program, bitcoin_script, bitcoin_script_ast, bitcoin_script_const,
+    bitcoin_script_include, bitcoin_script_rev, bitcoin_script_traced, guarded, script_template,
+    script_with_witness_template, try_bitcoin_script,
+};
+pub use compose::concat_with_separator;
+pub use lock_time::RelativeLockTime;
+pub use multisig::{bip67_multisig, checked_multisig, MultisigError};
+pub use nested::P2shP2wsh;
+pub use program::{WitnessProgram, WitnessProgramError};
+#[cfg(feature = "introspection")]
+pub use pushable::Serialized;
+pub use pushable::{Each, Minimal, Pushable, ScriptFragment, TryPushable};
+pub use script_num::ScriptNum;
+pub use validate::{validate_roundtrip, Error};
+pub use witness::WitnessItem;
@@ -0,0 +1,73 @@
+use crate::Pushable;
+use bitcoin::blockdata::script::Builder;
+use bitcoin::Sequence;
+
+/// A relative lock time for `OP_CHECKSEQUENCEVERIFY`, either a number of
+/// blocks or a number of 512-second intervals.
+///
+/// This version of `bitcoin` has no relative lock time type, so this one
+/// stands in for it. It is pushed as the script number of the matching
+/// sequence value, which has the type flag (bit 22) set for time based locks.
+///
+/// ```rust
+/// # #![feature(proc_macro_hygiene)]
+/// use bitcoin_script::{bitcoin_script, RelativeLockTime};
+///
+/// let script = bitcoin_script!(<RelativeLockTime::Blocks(144)> OP_CSV);
+/// assert_eq!(script.to_bytes(), vec![0x02, 0x90, 0x00, 0xb2]);
+///
+/// let script = bitcoin_script!(<RelativeLockTime::Time(2)> OP_CSV);
+/// assert_eq!(script.to_bytes(), vec![0x03, 0x02, 0x00, 0x40, 0xb2]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RelativeLockTime {
+    /// A number of blocks.
+    Blocks(u16),
+    /// A number of 512-second intervals.
+    Time(u16),
+}
+
+impl RelativeLockTime {
+    /// The sequence value which encodes the lock time, as used in a
+    /// transaction input.
+    pub fn to_sequence(self) -> Sequence {
+        match self {
+            RelativeLockTime::Blocks(blocks) => Sequence::from_height(blocks),
+            RelativeLockTime::Time(intervals) => Sequence::from_512_second_intervals(intervals),
+        }
+    }
+}
+
+impl From<RelativeLockTime> for Sequence {
+    fn from(lock_time: RelativeLockTime) -> Self {
+        lock_time.to_sequence()
+    }
+}
+
+impl Pushable for RelativeLockTime {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        self.to_sequence().bitcoin_script_push(builder)
+    }
+
+    fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+        self.to_sequence().bitcoin_script_push_be(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_lock_time_sequence() {
+        assert_eq!(RelativeLockTime::Blocks(144).to_sequence(), Sequence(144));
+        assert_eq!(
+            RelativeLockTime::Time(2).to_sequence(),
+            Sequence((1 << 22) | 2)
+        );
+        assert_eq!(
+            Sequence::from(RelativeLockTime::Time(u16::MAX)),
+            Sequence(0x0040_ffff)
+        );
+    }
+}
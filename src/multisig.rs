@@ -0,0 +1,207 @@
+use bitcoin::blockdata::opcodes::all::OP_CHECKMULTISIG;
+use bitcoin::blockdata::script::Builder;
+use bitcoin::{PublicKey, Script};
+use std::fmt;
+
+/// The most keys `OP_CHECKMULTISIG` accepts.
+const MAX_KEYS: usize = 20;
+
+/// Describes why a multisig script could not be assembled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultisigError {
+    /// The key at `index` already appeared earlier in the key list.
+    DuplicateKey { index: usize, key: PublicKey },
+    /// The threshold is zero or larger than the number of keys.
+    InvalidThreshold { threshold: usize, keys: usize },
+    /// More keys were given than `OP_CHECKMULTISIG` accepts.
+    TooManyKeys(usize),
+}
+
+impl fmt::Display for MultisigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MultisigError::DuplicateKey { index, key } => {
+                write!(f, "duplicate multisig key {} at index {}", key, index)
+            }
+            MultisigError::InvalidThreshold { threshold, keys } => write!(
+                f,
+                "invalid multisig threshold: {} of {} keys",
+                threshold, keys
+            ),
+            MultisigError::TooManyKeys(keys) => write!(
+                f,
+                "too many multisig keys: {} (at most {} are allowed)",
+                keys, MAX_KEYS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MultisigError {}
+
+/// Assembles the bare multisig script `threshold <key>... n OP_CHECKMULTISIG`,
+/// failing if any key appears more than once (which is almost always a
+/// mistake, and reduces the effective number of signers) or if the
+/// threshold or key count is out of range.
+///
+/// [`multisig_checked!`](crate::multisig_checked) accepts the keys as
+/// separate arguments.
+pub fn checked_multisig(threshold: usize, keys: &[PublicKey]) -> Result<Script, MultisigError> {
+    if keys.len() > MAX_KEYS {
+        return Err(MultisigError::TooManyKeys(keys.len()));
+    }
+    if threshold == 0 || threshold > keys.len() {
+        return Err(MultisigError::InvalidThreshold {
+            threshold,
+            keys: keys.len(),
+        });
+    }
+    for (index, key) in keys.iter().enumerate() {
+        if keys[..index].contains(key) {
+            return Err(MultisigError::DuplicateKey { index, key: *key });
+        }
+    }
+
+    let builder = keys
+        .iter()
+        .fold(Builder::new().push_int(threshold as i64), |builder, key| {
+            builder.push_key(key)
+        });
+    Ok(builder
+        .push_int(keys.len() as i64)
+        .push_opcode(OP_CHECKMULTISIG)
+        .into_script())
+}
+
+/// Like [`checked_multisig`], but first sorts the keys lexicographically by
+/// their serialization as required by BIP67, so the script (and so its
+/// address) doesn't depend on the order the keys were given in.
+///
+/// [`multisig_bip67!`](crate::multisig_bip67) accepts the keys as separate
+/// arguments.
+pub fn bip67_multisig(threshold: usize, keys: &[PublicKey]) -> Result<Script, MultisigError> {
+    let mut keys = keys.to_vec();
+    keys.sort_by_cached_key(|key| key.to_bytes());
+    checked_multisig(threshold, &keys)
+}
+
+/// Assembles a bare multisig script from a threshold and a list of keys,
+/// returning a [`MultisigError`] if a key is repeated. See
+/// [`checked_multisig`].
+///
+/// ```rust
+/// # #![feature(proc_macro_hygiene)]
+/// # use bitcoin_script::{multisig_checked, MultisigError};
+/// # use std::str::FromStr;
+/// let a = bitcoin::PublicKey::from_str(
+///     "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+/// )
+/// .unwrap();
+///
+/// let err = multisig_checked!(1, a, a).unwrap_err();
+/// assert_eq!(err, MultisigError::DuplicateKey { index: 1, key: a });
+/// ```
+#[macro_export]
+macro_rules! multisig_checked {
+    ($threshold:expr, $($key:expr),+ $(,)?) => {
+        $crate::checked_multisig($threshold, &[$($key),+])
+    };
+}
+
+/// Assembles a BIP67 multisig script from a threshold and a list of keys,
+/// which are sorted by their serialization. See [`bip67_multisig`].
+#[macro_export]
+macro_rules! multisig_bip67 {
+    ($threshold:expr, $($key:expr),+ $(,)?) => {
+        $crate::bip67_multisig($threshold, &[$($key),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn key(hex: &str) -> PublicKey {
+        PublicKey::from_str(hex).unwrap()
+    }
+
+    fn keys() -> Vec<PublicKey> {
+        vec![
+            key("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"),
+            key("02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5"),
+            key("02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9"),
+        ]
+    }
+
+    #[test]
+    fn checked_multisig_valid() {
+        let keys = keys();
+        let script = checked_multisig(2, &keys).unwrap();
+
+        let expected = Builder::new()
+            .push_int(2)
+            .push_key(&keys[0])
+            .push_key(&keys[1])
+            .push_key(&keys[2])
+            .push_int(3)
+            .push_opcode(OP_CHECKMULTISIG)
+            .into_script();
+        assert_eq!(script, expected);
+    }
+
+    #[test]
+    fn checked_multisig_duplicate() {
+        let mut keys = keys();
+        keys.push(keys[1]);
+
+        let err = checked_multisig(2, &keys).unwrap_err();
+        assert_eq!(
+            err,
+            MultisigError::DuplicateKey {
+                index: 3,
+                key: keys[1],
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "duplicate multisig key 02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5 at index 3"
+        );
+    }
+
+    #[test]
+    fn bip67_multisig_sorted() {
+        let keys = keys();
+        let uncompressed = key(concat!(
+            "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+        ));
+        let shuffled = vec![keys[2], uncompressed, keys[0], keys[1]];
+
+        let script = bip67_multisig(2, &shuffled).unwrap();
+        assert_eq!(
+            script,
+            checked_multisig(2, &[keys[0], keys[1], keys[2], uncompressed]).unwrap()
+        );
+    }
+
+    #[test]
+    fn checked_multisig_threshold() {
+        let keys = keys();
+
+        assert_eq!(
+            checked_multisig(0, &keys),
+            Err(MultisigError::InvalidThreshold {
+                threshold: 0,
+                keys: 3,
+            })
+        );
+        assert_eq!(
+            checked_multisig(4, &keys),
+            Err(MultisigError::InvalidThreshold {
+                threshold: 4,
+                keys: 3,
+            })
+        );
+    }
+}
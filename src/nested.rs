@@ -0,0 +1,44 @@
+use bitcoin::Script;
+
+/// The scripts for spending to a P2WSH witness script nested in P2SH.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct P2shP2wsh {
+    /// The P2SH output script, committing to `redeem_script`.
+    pub script_pubkey: Script,
+    /// The P2WSH witness program, pushed as the only item of the scriptSig.
+    pub redeem_script: Script,
+    /// The inner script, given as the last item of the witness.
+    pub witness_script: Script,
+}
+
+impl P2shP2wsh {
+    /// Wraps a witness script in P2WSH and then in P2SH.
+    pub fn new(witness_script: Script) -> Self {
+        let redeem_script = Script::new_v0_p2wsh(&witness_script.wscript_hash());
+        let script_pubkey = Script::new_p2sh(&redeem_script.script_hash());
+
+        P2shP2wsh {
+            script_pubkey,
+            redeem_script,
+            witness_script,
+        }
+    }
+}
+
+/// Builds a witness script with `bitcoin_script!` and nests it in P2SH-P2WSH,
+/// evaluating to a [`P2shP2wsh`].
+///
+/// ```rust
+/// # #![feature(proc_macro_hygiene)]
+/// # use bitcoin_script::p2sh_p2wsh_script;
+/// let nested = p2sh_p2wsh_script!(OP_PUSHNUM_1);
+/// assert_eq!(nested.witness_script.to_bytes(), vec![0x51]);
+/// assert!(nested.redeem_script.is_v0_p2wsh());
+/// assert!(nested.script_pubkey.is_p2sh());
+/// ```
+#[macro_export]
+macro_rules! p2sh_p2wsh_script {
+    ($($script:tt)*) => {
+        $crate::P2shP2wsh::new($crate::bitcoin_script!($($script)*))
+    };
+}
@@ -0,0 +1,126 @@
+use crate::Pushable;
+use bitcoin::blockdata::script::Builder;
+use bitcoin::util::address::WitnessVersion;
+use std::fmt;
+
+/// Describes why a witness program is invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WitnessProgramError {
+    /// The program is shorter than 2 bytes or longer than 40.
+    InvalidLength(usize),
+    /// A version 0 program is neither 20 bytes (P2WPKH) nor 32 (P2WSH).
+    InvalidV0Length(usize),
+    /// A version 1 program isn't 32 bytes (a taproot output key). Other
+    /// lengths are valid but unencumbered, so anyone could spend them.
+    InvalidV1Length(usize),
+}
+
+impl fmt::Display for WitnessProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WitnessProgramError::InvalidLength(len) => write!(
+                f,
+                "invalid witness program length: {} (expected 2 to 40 bytes)",
+                len
+            ),
+            WitnessProgramError::InvalidV0Length(len) => write!(
+                f,
+                "invalid version 0 witness program length: {} (expected 20 or 32 bytes)",
+                len
+            ),
+            WitnessProgramError::InvalidV1Length(len) => write!(
+                f,
+                "invalid version 1 witness program length: {} (expected 32 bytes)",
+                len
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WitnessProgramError {}
+
+/// A segwit version and a program whose length is valid for it, for writing
+/// a witness output script like `0 <program>`, or `<program.version()>
+/// <program>` to take the version from the program.
+///
+/// ```rust
+/// # #![feature(proc_macro_hygiene)]
+/// use bitcoin::util::address::WitnessVersion;
+/// use bitcoin_script::{bitcoin_script, WitnessProgram};
+///
+/// let program = WitnessProgram::new(WitnessVersion::V0, vec![0xab; 20]).unwrap();
+/// let script = bitcoin_script!(<program.version()> <program>);
+/// assert!(script.is_v0_p2wpkh());
+///
+/// assert!(WitnessProgram::new(WitnessVersion::V0, vec![0xab; 21]).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WitnessProgram {
+    version: WitnessVersion,
+    program: Vec<u8>,
+}
+
+impl WitnessProgram {
+    /// Checks that the program length is valid for the version.
+    pub fn new(version: WitnessVersion, program: Vec<u8>) -> Result<Self, WitnessProgramError> {
+        let len = program.len();
+        match version {
+            _ if !(2..=40).contains(&len) => Err(WitnessProgramError::InvalidLength(len)),
+            WitnessVersion::V0 if len != 20 && len != 32 => {
+                Err(WitnessProgramError::InvalidV0Length(len))
+            }
+            WitnessVersion::V1 if len != 32 => Err(WitnessProgramError::InvalidV1Length(len)),
+            _ => Ok(WitnessProgram { version, program }),
+        }
+    }
+
+    /// The segwit version.
+    pub fn version(&self) -> WitnessVersion {
+        self.version
+    }
+
+    /// The program bytes.
+    pub fn program(&self) -> &[u8] {
+        &self.program
+    }
+}
+
+// only the program bytes, since the version is usually written as an opcode
+impl Pushable for WitnessProgram {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(&self.program)
+    }
+}
+
+// `OP_0` to `OP_16`
+impl Pushable for WitnessVersion {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_opcode((*self).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn witness_program_lengths() {
+        assert!(WitnessProgram::new(WitnessVersion::V0, vec![0; 20]).is_ok());
+        assert!(WitnessProgram::new(WitnessVersion::V0, vec![0; 32]).is_ok());
+        assert!(WitnessProgram::new(WitnessVersion::V1, vec![0; 32]).is_ok());
+        assert!(WitnessProgram::new(WitnessVersion::V2, vec![0; 2]).is_ok());
+
+        assert_eq!(
+            WitnessProgram::new(WitnessVersion::V0, vec![0; 33]),
+            Err(WitnessProgramError::InvalidV0Length(33))
+        );
+        assert_eq!(
+            WitnessProgram::new(WitnessVersion::V1, vec![0; 20]),
+            Err(WitnessProgramError::InvalidV1Length(20))
+        );
+        assert_eq!(
+            WitnessProgram::new(WitnessVersion::V2, vec![0; 41]),
+            Err(WitnessProgramError::InvalidLength(41))
+        );
+    }
+}
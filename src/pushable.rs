@@ -0,0 +1,703 @@
+use bitcoin::blockdata::script::Builder;
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// A value which can be interpolated into a script with an escape, e.g.
+/// `<value>`.
+///
+/// Besides the built-in impls, this can be implemented for other types, or
+/// derived for structs with `#[derive(Pushable)]`. Escapes only borrow their
+/// value, and the builder returned by `bitcoin_script_push` is appended to
+/// the script in place of the escape, so an impl may push any number of
+/// items (or none).
+///
+/// ```rust
+/// # #![feature(proc_macro_hygiene)]
+/// use bitcoin::blockdata::script::Builder;
+/// use bitcoin_script::{bitcoin_script, Pushable};
+///
+/// struct Timelock(u32);
+///
+/// impl Pushable for Timelock {
+///     fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+///         builder.push_int(self.0 as i64)
+///     }
+/// }
+///
+/// let script = bitcoin_script!(<Timelock(144)> OP_CSV);
+/// assert_eq!(script.to_bytes(), vec![0x02, 0x90, 0x00, 0xb2]);
+/// ```
+///
+/// Secret keys are deliberately not `Pushable`, since scripts are published
+/// on-chain, and the compile error for one suggests its public key instead.
+///
+/// ```compile_fail
+/// # #![feature(proc_macro_hygiene)]
+/// use bitcoin::secp256k1::SecretKey;
+/// use bitcoin_script::bitcoin_script;
+///
+/// let secret_key = SecretKey::from_slice(&[1; 32]).unwrap();
+/// let script = bitcoin_script!(<secret_key> OP_CHECKSIG);
+/// ```
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be pushed into a script",
+    label = "this type doesn't implement `Pushable`",
+    note = "secret keys are never pushable since scripts are published on-chain, push the public key instead (e.g. `<secret_key.public_key(&secp)>`)"
+)]
+pub trait Pushable {
+    /// Appends the value to `builder`, returning the extended builder.
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder;
+
+    /// Appends the value to a script which starts with the `@be;` directive.
+    /// Only integers are pushed differently, so wrappers should forward this
+    /// to their contents.
+    #[doc(hidden)]
+    fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+        self.bitcoin_script_push(builder)
+    }
+}
+
+/// A value which can be interpolated into an escape of `try_bitcoin_script!`:
+/// any [`Pushable`] value, which can't fail, or a `Result` of one, whose
+/// error is converted into `E` with `From` and returned from the macro.
+///
+/// The error is cloned out of the borrowed `Result`, so it must be `Clone`.
+pub trait TryPushable<E> {
+    /// Appends the value to `builder`, or returns its error.
+    fn bitcoin_script_try_push(&self, builder: Builder) -> Result<Builder, E>;
+
+    /// Like `bitcoin_script_try_push`, for scripts starting with `@be;`.
+    #[doc(hidden)]
+    fn bitcoin_script_try_push_be(&self, builder: Builder) -> Result<Builder, E>;
+}
+
+impl<T: Pushable + ?Sized, E> TryPushable<E> for T {
+    fn bitcoin_script_try_push(&self, builder: Builder) -> Result<Builder, E> {
+        Ok(self.bitcoin_script_push(builder))
+    }
+
+    fn bitcoin_script_try_push_be(&self, builder: Builder) -> Result<Builder, E> {
+        Ok(self.bitcoin_script_push_be(builder))
+    }
+}
+
+// `Result` isn't `Pushable`, so this doesn't overlap with the impl above
+impl<T: Pushable, E: Clone, F: From<E>> TryPushable<F> for Result<T, E> {
+    fn bitcoin_script_try_push(&self, builder: Builder) -> Result<Builder, F> {
+        match self {
+            Ok(value) => Ok(value.bitcoin_script_push(builder)),
+            Err(error) => Err(error.clone().into()),
+        }
+    }
+
+    fn bitcoin_script_try_push_be(&self, builder: Builder) -> Result<Builder, F> {
+        match self {
+            Ok(value) => Ok(value.bitcoin_script_push_be(builder)),
+            Err(error) => Err(error.clone().into()),
+        }
+    }
+}
+
+// references push the same as the value they point to (`&[u8]` has its own
+// impl, since `[u8]` isn't pushable)
+impl<T: Pushable + ?Sized> Pushable for &T {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        (**self).bitcoin_script_push(builder)
+    }
+
+    fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+        (**self).bitcoin_script_push_be(builder)
+    }
+}
+
+impl Pushable for &[u8] {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(self)
+    }
+}
+
+impl Pushable for Vec<u8> {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(self.as_ref())
+    }
+}
+
+// text is pushed as its UTF-8 bytes
+impl Pushable for str {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(self.as_bytes())
+    }
+}
+
+impl Pushable for String {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(self.as_bytes())
+    }
+}
+
+impl<const N: usize> Pushable for [u8; N] {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(self)
+    }
+}
+
+impl Pushable for i64 {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_int(*self)
+    }
+
+    fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+        match *self {
+            n @ -1..=16 => builder.push_int(n),
+            n => builder.push_slice(&n.to_be_bytes()),
+        }
+    }
+}
+
+// smaller integers are pushed the same way as the `i64` they convert to.
+// `i32` is included since it is what unsuffixed literals like `<1 + 1>` fall
+// back to when more than one integer type is pushable.
+macro_rules! impl_pushable_int {
+    ($($int:ty),*) => {
+        $(
+            impl Pushable for $int {
+                fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+                    i64::from(*self).bitcoin_script_push(builder)
+                }
+
+                fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+                    i64::from(*self).bitcoin_script_push_be(builder)
+                }
+            }
+        )*
+    };
+}
+
+impl_pushable_int!(u8, u16, u32, i8, i16, i32);
+
+// the largest number any opcode reads, as a 5-byte script number for
+// `OP_CHECKLOCKTIMEVERIFY` and `OP_CHECKSEQUENCEVERIFY`
+const MAX_SCRIPT_NUM: u64 = (1 << 39) - 1;
+
+// larger unsigned integers are checked instead of wrapping when converted,
+// panicking if they are out of range
+macro_rules! impl_pushable_checked_int {
+    ($($int:ty),*) => {
+        $(
+            impl Pushable for $int {
+                fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+                    checked_script_num(*self as u64).bitcoin_script_push(builder)
+                }
+
+                fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+                    checked_script_num(*self as u64).bitcoin_script_push_be(builder)
+                }
+            }
+        )*
+    };
+}
+
+impl_pushable_checked_int!(u64, usize);
+
+fn checked_script_num(n: u64) -> i64 {
+    if n > MAX_SCRIPT_NUM {
+        panic!(
+            "{} is out of range for a script number (the maximum is {})",
+            n, MAX_SCRIPT_NUM
+        );
+    }
+    n as i64
+}
+
+// the value in satoshis as a script number, with the same range check as
+// `u64` (so amounts above about 5497 BTC panic rather than wrap)
+impl Pushable for bitcoin::Amount {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        self.to_sat().bitcoin_script_push(builder)
+    }
+
+    fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+        self.to_sat().bitcoin_script_push_be(builder)
+    }
+}
+
+// `OP_TRUE` or `OP_FALSE`, i.e. `1` or the empty array
+impl Pushable for bool {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_int(*self as i64)
+    }
+}
+
+impl Pushable for bitcoin::PublicKey {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_key(self)
+    }
+}
+
+// compressed, as `bitcoin::PublicKey` would be for a compressed key
+impl Pushable for bitcoin::secp256k1::PublicKey {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(&self.serialize())
+    }
+}
+
+// the 32-byte x-coordinate, as used by tapscript signature checks
+impl Pushable for bitcoin::XOnlyPublicKey {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(&self.serialize())
+    }
+}
+
+impl Pushable for crate::ScriptNum {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(&self.to_bytes())
+    }
+}
+
+impl Pushable for bitcoin::util::taproot::ControlBlock {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(&self.serialize())
+    }
+}
+
+// pushed as a script number for `OP_CHECKSEQUENCEVERIFY`. Any `u32` fits in
+// the 5-byte numbers it accepts, so no value needs to be rejected.
+impl Pushable for bitcoin::Sequence {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_int(self.0.into())
+    }
+
+    fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+        i64::from(self.0).bitcoin_script_push_be(builder)
+    }
+}
+
+// the consensus value (a block height, or a timestamp from 500000000 on) as a
+// script number for `OP_CHECKLOCKTIMEVERIFY`, which reads numbers of up to 5
+// bytes, enough for any `u32`
+impl Pushable for bitcoin::LockTime {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_int(self.to_consensus_u32().into())
+    }
+
+    fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+        i64::from(self.to_consensus_u32()).bitcoin_script_push_be(builder)
+    }
+}
+
+impl Pushable for bitcoin::PackedLockTime {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_int(self.0.into())
+    }
+
+    fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+        i64::from(self.0).bitcoin_script_push_be(builder)
+    }
+}
+
+// pushes nothing for `None`
+impl<T: Pushable> Pushable for Option<T> {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        match self {
+            Some(value) => value.bitcoin_script_push(builder),
+            None => builder,
+        }
+    }
+
+    fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+        match self {
+            Some(value) => value.bitcoin_script_push_be(builder),
+            None => builder,
+        }
+    }
+}
+
+/// Pushes each item of a collection or iterator in order, e.g.
+/// `<Each(&keys)>` for a `Vec<PublicKey>`, `<Each(&preimages)>` for a
+/// `Vec<Vec<u8>>`, `<Each(&sigs)>` for a `Vec<EcdsaSig>` or
+/// `<Each(proof.iter().rev())>`.
+///
+/// A plain `Vec<T>` can't be pushed this way since `Vec<u8>` is already
+/// pushed as a single data element, so collections of any other type are
+/// always wrapped in `Each`. The wrapped value is cloned to iterate
+/// over it, which is cheap for references and most iterators.
+///
+/// ```rust
+/// # #![feature(proc_macro_hygiene)]
+/// use bitcoin_script::{bitcoin_script, Each};
+///
+/// let proof = vec![vec![1], vec![2, 3]];
+/// let script = bitcoin_script!(<Each(&proof)> <Each(1..=3)>);
+/// assert_eq!(script.to_bytes(), vec![1, 1, 2, 2, 3, 0x51, 0x52, 0x53]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Each<I>(pub I);
+
+impl<I> Pushable for Each<I>
+where
+    I: Clone + IntoIterator,
+    I::Item: Pushable,
+{
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        self.0
+            .clone()
+            .into_iter()
+            .fold(builder, |builder, item| item.bitcoin_script_push(builder))
+    }
+
+    fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+        self.0.clone().into_iter().fold(builder, |builder, item| {
+            item.bitcoin_script_push_be(builder)
+        })
+    }
+}
+
+/// Appends whatever a function adds to the builder, letting helpers
+/// contribute any sequence of opcodes and pushes at the escape's position,
+/// e.g. `<ScriptFragment(|b: Builder| b.push_opcode(OP_DROP))>`.
+///
+/// Escapes only borrow their value, so the function must be `Fn` rather than
+/// `FnOnce`, and it is called again each time the escape is evaluated.
+///
+/// ```rust
+/// # #![feature(proc_macro_hygiene)]
+/// use bitcoin::blockdata::opcodes::all::OP_DROP;
+/// use bitcoin::blockdata::script::Builder;
+/// use bitcoin_script::{bitcoin_script, ScriptFragment};
+///
+/// fn drop_n(n: usize) -> ScriptFragment<impl Fn(Builder) -> Builder> {
+///     ScriptFragment(move |builder: Builder| {
+///         (0..n).fold(builder, |builder, _| builder.push_opcode(OP_DROP))
+///     })
+/// }
+///
+/// let script = bitcoin_script!(<drop_n(2)> 1);
+/// assert_eq!(script.to_bytes(), vec![0x75, 0x75, 0x51]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ScriptFragment<F>(pub F);
+
+impl<F: Fn(Builder) -> Builder> Pushable for ScriptFragment<F> {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        (self.0)(builder)
+    }
+}
+
+/// Pushes bytes the way MINIMALDATA requires: a single byte from 1 to 16 as
+/// `OP_1` to `OP_16` and `0x81` as `OP_1NEGATE`, rather than as one-byte data
+/// pushes (which is how `Vec<u8>` and `&[u8]` are always pushed). Anything
+/// else is pushed as data, which for empty data is already `OP_0`.
+///
+/// ```rust
+/// # #![feature(proc_macro_hygiene)]
+/// use bitcoin_script::{bitcoin_script, Minimal};
+///
+/// let preimage = vec![5];
+/// let script = bitcoin_script!(<Minimal(&preimage)> <preimage>);
+/// assert_eq!(script.to_bytes(), vec![0x55, 1, 5]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Minimal<T>(pub T);
+
+impl<T: AsRef<[u8]>> Pushable for Minimal<T> {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        match self.0.as_ref() {
+            [n @ 1..=16] => builder.push_int(*n as i64),
+            [0x81] => builder.push_int(-1),
+            bytes => builder.push_slice(bytes),
+        }
+    }
+}
+
+// sets are pushed in iteration order, which is only deterministic for
+// `BTreeSet`, so there is deliberately no `HashSet` impl
+impl<T: Pushable> Pushable for BTreeSet<T> {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        self.iter()
+            .fold(builder, |builder, item| item.bitcoin_script_push(builder))
+    }
+
+    fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+        self.iter().fold(builder, |builder, item| {
+            item.bitcoin_script_push_be(builder)
+        })
+    }
+}
+
+// pushes the start, then the end
+impl Pushable for Range<i64> {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        let builder = self.start.bitcoin_script_push(builder);
+        self.end.bitcoin_script_push(builder)
+    }
+
+    fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+        let builder = self.start.bitcoin_script_push_be(builder);
+        self.end.bitcoin_script_push_be(builder)
+    }
+}
+
+// pushes nothing, so escapes can evaluate to `()` when there is nothing to add
+impl Pushable for () {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder
+    }
+}
+
+// tuples push each element in order, so helpers can return grouped pushes
+// (e.g. a signature and its key). The `(Signature, EcdsaSighashType)` pair
+// doesn't overlap with these since neither of its types is pushable alone.
+macro_rules! impl_pushable_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: Pushable),+> Pushable for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+                let ($($name,)+) = self;
+                $(let builder = $name.bitcoin_script_push(builder);)+
+                builder
+            }
+
+            #[allow(non_snake_case)]
+            fn bitcoin_script_push_be(&self, builder: Builder) -> Builder {
+                let ($($name,)+) = self;
+                $(let builder = $name.bitcoin_script_push_be(builder);)+
+                builder
+            }
+        }
+    };
+}
+
+impl_pushable_tuple!(A, B);
+impl_pushable_tuple!(A, B, C);
+impl_pushable_tuple!(A, B, C, D);
+impl_pushable_tuple!(A, B, C, D, E);
+impl_pushable_tuple!(A, B, C, D, E, F);
+
+// opcodes chosen at runtime are appended as the opcode itself
+impl Pushable for bitcoin::blockdata::opcodes::All {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_opcode(*self)
+    }
+}
+
+// instructions parsed from another script (or produced by other tooling) are
+// appended as they were, though pushes are re-encoded minimally
+impl Pushable for bitcoin::blockdata::script::Instruction<'_> {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        use bitcoin::blockdata::script::Instruction;
+
+        match self {
+            Instruction::PushBytes(bytes) => builder.push_slice(bytes),
+            Instruction::Op(opcode) => builder.push_opcode(*opcode),
+        }
+    }
+}
+
+// scripts are appended verbatim rather than pushed as data
+impl Pushable for bitcoin::Script {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        let mut bytes = builder.into_script().into_bytes();
+        bytes.extend_from_slice(self.as_bytes());
+        Builder::from(bytes)
+    }
+}
+
+// signatures are pushed as their DER encoding followed by the sighash type
+// byte
+impl Pushable for bitcoin::EcdsaSig {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(&self.to_vec())
+    }
+}
+
+impl Pushable
+    for (
+        bitcoin::secp256k1::ecdsa::Signature,
+        bitcoin::EcdsaSighashType,
+    )
+{
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        let (sig, hash_ty) = *self;
+        bitcoin::EcdsaSig { sig, hash_ty }.bitcoin_script_push(builder)
+    }
+}
+
+// signatures are pushed as their 64 bytes, followed by the sighash type byte
+// unless it is `SIGHASH_DEFAULT`
+impl Pushable for bitcoin::SchnorrSig {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(&self.to_vec())
+    }
+}
+
+// hashes are pushed as their bytes in internal order, which for the double
+// SHA256 types is the reverse of their usual hex display
+macro_rules! impl_pushable_hash {
+    ($($hash:ty),*) => {
+        $(
+            impl Pushable for $hash {
+                fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+                    builder.push_slice(&self[..])
+                }
+            }
+        )*
+    };
+}
+
+impl_pushable_hash!(
+    bitcoin::PubkeyHash,
+    bitcoin::ScriptHash,
+    bitcoin::WPubkeyHash,
+    bitcoin::WScriptHash,
+    bitcoin::hashes::sha256::Hash,
+    bitcoin::hashes::sha256d::Hash,
+    bitcoin::hashes::ripemd160::Hash,
+    bitcoin::hashes::hash160::Hash,
+    bitcoin::util::taproot::TapLeafHash,
+    bitcoin::util::taproot::TapBranchHash,
+    bitcoin::util::taproot::TapTweakHash,
+    bitcoin::Txid,
+    bitcoin::Wtxid
+);
+
+// consensus-serialized transaction components, for introspection scripts
+#[cfg(feature = "introspection")]
+impl Pushable for bitcoin::OutPoint {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(&bitcoin::consensus::encode::serialize(self))
+    }
+}
+
+#[cfg(feature = "introspection")]
+impl Pushable for bitcoin::TxOut {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(&bitcoin::consensus::encode::serialize(self))
+    }
+}
+
+/// Pushes the consensus serialization of a borrowed
+/// `bitcoin::consensus::Encodable` value as a single element, e.g.
+/// `<Serialized(&tx)>` for a whole transaction or `<Serialized(&header)>` for
+/// a block header (with the `introspection` feature).
+///
+/// This is a wrapper rather than an impl for every `Encodable` type, since
+/// some of them (like `Vec<u8>`, whose serialization is length-prefixed) are
+/// already pushed differently.
+#[cfg(feature = "introspection")]
+#[derive(Debug, Clone, Copy)]
+pub struct Serialized<'a, T: ?Sized>(pub &'a T);
+
+#[cfg(feature = "introspection")]
+impl<T: bitcoin::consensus::Encodable + ?Sized> Pushable for Serialized<'_, T> {
+    fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+        builder.push_slice(&bitcoin::consensus::encode::serialize(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(value: impl Pushable) -> Vec<u8> {
+        value
+            .bitcoin_script_push(Builder::new())
+            .into_script()
+            .into_bytes()
+    }
+
+    fn push_be(value: impl Pushable) -> Vec<u8> {
+        value
+            .bitcoin_script_push_be(Builder::new())
+            .into_script()
+            .into_bytes()
+    }
+
+    #[test]
+    fn push_int() {
+        assert_eq!(push(1000i64), vec![2, 0xe8, 0x03]);
+        assert_eq!(push_be(1000i64), vec![8, 0, 0, 0, 0, 0, 0, 0x03, 0xe8]);
+        assert_eq!(push_be(5i64), vec![0x55]);
+    }
+
+    #[test]
+    fn push_checked_int() {
+        assert_eq!(push(1000u64), vec![2, 0xe8, 0x03]);
+        assert_eq!(
+            push(MAX_SCRIPT_NUM as usize),
+            vec![5, 0xff, 0xff, 0xff, 0xff, 0x7f]
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "549755813888 is out of range for a script number (the maximum is 549755813887)"
+    )]
+    fn push_checked_int_overflow() {
+        push(MAX_SCRIPT_NUM + 1);
+    }
+
+    #[test]
+    fn push_amount() {
+        let amount = bitcoin::Amount::from_sat(100_000);
+        assert_eq!(push(amount), vec![3, 0xa0, 0x86, 0x01]);
+        assert_eq!(push_be(amount), push_be(100_000u64));
+        assert_eq!(push(bitcoin::Amount::ZERO), vec![0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "is out of range for a script number")]
+    fn push_amount_overflow() {
+        push(bitcoin::Amount::MAX_MONEY);
+    }
+
+    #[test]
+    fn push_option() {
+        assert_eq!(push(Some(1000i64)), vec![2, 0xe8, 0x03]);
+        assert_eq!(push(None::<i64>), vec![]);
+        assert_eq!(
+            push_be(Some(1000i64)),
+            vec![8, 0, 0, 0, 0, 0, 0, 0x03, 0xe8]
+        );
+    }
+
+    #[test]
+    fn push_byte_vecs() {
+        let stack = vec![vec![1, 2], vec![], vec![3]];
+        assert_eq!(push(Each(&stack)), vec![2, 1, 2, 0, 1, 3]);
+        assert_eq!(push(Each(&stack[1..])), vec![0, 1, 3]);
+        assert_eq!(push(Each([&[4u8][..], &[5, 6]])), vec![1, 4, 2, 5, 6]);
+    }
+
+    #[test]
+    fn push_minimal() {
+        assert_eq!(push(Minimal(vec![])), vec![0]);
+        assert_eq!(push(Minimal([1])), vec![0x51]);
+        assert_eq!(push(Minimal([16])), vec![0x60]);
+        assert_eq!(push(Minimal([0x81])), vec![0x4f]);
+        assert_eq!(push(Minimal([0])), vec![1, 0]);
+        assert_eq!(push(Minimal([17])), vec![1, 17]);
+        assert_eq!(push(Minimal(&[1, 2][..])), vec![2, 1, 2]);
+    }
+
+    #[test]
+    fn push_unit() {
+        assert_eq!(push(()), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn push_tuple() {
+        assert_eq!(push((1i64, vec![2u8], -1i64)), vec![0x51, 1, 2, 0x4f]);
+        assert_eq!(
+            push_be((1000i64, 2i64)),
+            vec![8, 0, 0, 0, 0, 0, 0, 0x03, 0xe8, 0x52]
+        );
+    }
+
+    #[test]
+    fn push_range_be() {
+        assert_eq!(
+            push_be(5..1000),
+            vec![0x55, 8, 0, 0, 0, 0, 0, 0, 0x03, 0xe8]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+/// An integer which is pushed as a minimally-encoded script number data
+/// element, rather than with the small-integer opcodes.
+///
+/// Interpolating a plain `i64` uses the most compact encoding, so e.g. `5`
+/// becomes `OP_PUSHNUM_5`. Wrapping it as `ScriptNum(5)` always pushes the
+/// CScriptNum bytes instead (`0x0105`), which matters when the script itself
+/// must match a byte layout produced elsewhere. Zero is encoded as the empty
+/// byte array, i.e. `OP_0`, in both cases.
+///
+/// The same applies to `-1`: as an `i64` it is pushed with `OP_PUSHNUM_NEG1`
+/// (`OP_1NEGATE`, `0x4f`), while `ScriptNum(-1)` pushes the data element
+/// `0x81` (`0x0181`). Both forms leave the identical element `0x81` on the
+/// stack, so `OP_EQUAL` and the arithmetic opcodes can't tell them apart;
+/// only the script encoding differs. Other negative numbers have no opcode
+/// form and are always pushed as data.
+///
+/// Note that pushes which could have used an opcode are non-minimal, so
+/// scripts containing them are rejected by the standardness `MINIMALDATA`
+/// rule when executed.
+///
+/// ```rust
+/// # #![feature(proc_macro_hygiene)]
+/// use bitcoin_script::{bitcoin_script, ScriptNum};
+///
+/// let script = bitcoin_script!(<ScriptNum(5)> <5>);
+/// assert_eq!(script.to_bytes(), vec![0x01, 0x05, 0x55]);
+///
+/// let script = bitcoin_script!(<ScriptNum(-1)> -1);
+/// assert_eq!(script.to_bytes(), vec![0x01, 0x81, 0x4f]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ScriptNum(pub i64);
+
+// the same encoding the macro uses for constants
+mod encoding {
+    include!("../macro/src/script_num.rs");
+}
+
+impl ScriptNum {
+    /// Encodes the number as a minimal script number (CScriptNum), e.g. `255`
+    /// is `0xff00` and `-1` is `0x81`. Zero is the empty byte array. Any `i64`
+    /// can be encoded, using up to 9 bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encoding::minimal(self.0)
+    }
+
+    /// Encodes the number as a script number of exactly `width` bytes, padding
+    /// the magnitude with zero bytes before the sign bit rather than using the
+    /// minimal encoding, e.g. `255` in 4 bytes is `0xff000000`. Returns `None`
+    /// if the number doesn't fit, or if `width` is 0.
+    pub fn to_padded_bytes(&self, width: usize) -> Option<Vec<u8>> {
+        encoding::padded(self.0, width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_bytes() {
+        assert_eq!(ScriptNum(0).to_bytes(), Vec::<u8>::new());
+        assert_eq!(ScriptNum(-1).to_bytes(), vec![0x81]);
+        assert_eq!(ScriptNum(255).to_bytes(), vec![0xff, 0x00]);
+        assert_eq!(ScriptNum(-255).to_bytes(), vec![0xff, 0x80]);
+        assert_eq!(
+            ScriptNum(1 << 39).to_bytes(),
+            vec![0x00, 0x00, 0x00, 0x00, 0x80, 0x00]
+        );
+        assert_eq!(
+            ScriptNum(i64::MIN).to_bytes(),
+            vec![0, 0, 0, 0, 0, 0, 0, 0x80, 0x80]
+        );
+    }
+
+    #[test]
+    fn padded_bytes() {
+        assert_eq!(ScriptNum(255).to_padded_bytes(4), Some(vec![0xff, 0, 0, 0]));
+        assert_eq!(ScriptNum(-1).to_padded_bytes(2), Some(vec![0x01, 0x80]));
+        assert_eq!(ScriptNum(0).to_padded_bytes(1), Some(vec![0]));
+        assert_eq!(ScriptNum(127).to_padded_bytes(1), Some(vec![0x7f]));
+        assert_eq!(
+            ScriptNum(i64::MIN + 1).to_padded_bytes(8),
+            Some(vec![0xff; 8])
+        );
+    }
+
+    #[test]
+    fn padded_bytes_overflow() {
+        assert_eq!(ScriptNum(128).to_padded_bytes(1), None);
+        assert_eq!(ScriptNum(-256).to_padded_bytes(1), None);
+        assert_eq!(ScriptNum(1).to_padded_bytes(0), None);
+    }
+}
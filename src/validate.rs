@@ -0,0 +1,146 @@
+use bitcoin::blockdata::opcodes::{self, All as Opcode};
+use bitcoin::blockdata::script::{self, Instruction};
+use bitcoin::Script;
+use std::fmt;
+
+/// Describes why a script failed structural validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The push opcode at `offset` declares more data than remains in the
+    /// script.
+    TruncatedPush { offset: usize, opcode: Opcode },
+    /// rust-bitcoin rejected the instruction starting at `offset`.
+    InvalidInstruction {
+        offset: usize,
+        opcode: Opcode,
+        error: script::Error,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::TruncatedPush { offset, opcode } => write!(
+                f,
+                "truncated push: {:?} at byte {} extends past the end of the script",
+                opcode, offset
+            ),
+            Error::InvalidInstruction {
+                offset,
+                opcode,
+                error,
+            } => write!(
+                f,
+                "invalid instruction: {:?} at byte {} ({})",
+                opcode, offset, error
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Checks that `script` decodes cleanly through rust-bitcoin's
+/// [`Script::instructions`] iterator, i.e. that every push is complete and
+/// no decoding errors occur before the end of the script.
+///
+/// Useful for asserting that a generated (or externally-sourced) script is
+/// structurally well-formed before it is committed to.
+pub fn validate_roundtrip(script: &Script) -> Result<(), Error> {
+    let bytes = script.as_bytes();
+    let mut offset = 0;
+
+    for instruction in script.instructions() {
+        let opcode = Opcode::from(bytes[offset]);
+        match instruction {
+            Ok(Instruction::Op(_)) => offset += 1,
+            Ok(Instruction::PushBytes(data)) => {
+                offset += push_header_len(opcode) + data.len();
+            }
+            Err(script::Error::EarlyEndOfScript) => {
+                return Err(Error::TruncatedPush { offset, opcode });
+            }
+            Err(error) => {
+                return Err(Error::InvalidInstruction {
+                    offset,
+                    opcode,
+                    error,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// number of bytes used by a push opcode and its length prefix
+fn push_header_len(opcode: Opcode) -> usize {
+    match opcode {
+        opcodes::all::OP_PUSHDATA1 => 2,
+        opcodes::all::OP_PUSHDATA2 => 3,
+        opcodes::all::OP_PUSHDATA4 => 5,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::blockdata::opcodes::all as opcodes;
+    use bitcoin::blockdata::script::Builder;
+
+    #[test]
+    fn validate_empty() {
+        assert_eq!(validate_roundtrip(&Script::new()), Ok(()));
+    }
+
+    #[test]
+    fn validate_valid() {
+        let script = Builder::new()
+            .push_opcode(opcodes::OP_DUP)
+            .push_int(0)
+            .push_int(1234)
+            .push_slice(&[0xab; 80])
+            .push_slice(&[0xcd; 300])
+            .push_opcode(opcodes::OP_CHECKSIG)
+            .into_script();
+
+        assert_eq!(validate_roundtrip(&script), Ok(()));
+    }
+
+    #[test]
+    fn validate_truncated() {
+        let mut bytes = Builder::new()
+            .push_opcode(opcodes::OP_DUP)
+            .push_slice(&[1, 2, 3, 4])
+            .into_script()
+            .into_bytes();
+        bytes.pop();
+
+        assert_eq!(
+            validate_roundtrip(&Script::from(bytes)),
+            Err(Error::TruncatedPush {
+                offset: 1,
+                opcode: opcodes::OP_PUSHBYTES_4,
+            })
+        );
+    }
+
+    #[test]
+    fn validate_truncated_pushdata() {
+        let script = Script::from(vec![0x76, 0x4d, 0x01]);
+
+        let err = validate_roundtrip(&script).unwrap_err();
+        assert_eq!(
+            err,
+            Error::TruncatedPush {
+                offset: 1,
+                opcode: opcodes::OP_PUSHDATA2,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "truncated push: OP_PUSHDATA2 at byte 1 extends past the end of the script"
+        );
+    }
+}
@@ -0,0 +1,10 @@
+/// An item of the witness stack template built by
+/// `script_with_witness_template!`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WitnessItem {
+    /// An element whose value is known when the template is built.
+    Bytes(Vec<u8>),
+    /// An element which has to be filled in before spending, e.g. a
+    /// signature, named after the placeholder without its leading `_`.
+    Placeholder(String),
+}
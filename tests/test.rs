@@ -1,6 +1,10 @@
-#![feature(proc_macro_hygiene)]
-
-use bitcoin_script::bitcoin_script;
+use bitcoin_script::{
+    assert_witness_program, bitcoin_script, bitcoin_script_ast, bitcoin_script_const,
+    bitcoin_script_include, bitcoin_script_rev, bitcoin_script_traced, guarded, multisig_bip67,
+    multisig_checked, p2sh_p2wsh_script, script_template, script_with_witness_template, size_check,
+    try_bitcoin_script, Each, MultisigError, Pushable, RelativeLockTime, ScriptFragment, ScriptNum,
+    WitnessItem,
+};
 
 #[test]
 fn fixture() {
@@ -21,3 +25,1544 @@ fn fixture() {
         vec![169, 2, 210, 4, 2, 255, 0, 79, 2, 255, 128, 2, 171, 205, 82, 4, 1, 2, 3, 4]
     );
 }
+
+#[cfg(feature = "introspection")]
+#[test]
+fn push_outpoint() {
+    use bitcoin::hashes::Hash;
+    use bitcoin::{OutPoint, Txid};
+
+    let outpoint = OutPoint::new(Txid::from_inner([0x11; 32]), 1);
+    let script = bitcoin_script!(<outpoint>);
+
+    let mut expected = vec![36];
+    expected.extend(&[0x11; 32]);
+    expected.extend(&[1, 0, 0, 0]);
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[cfg(feature = "introspection")]
+#[test]
+fn push_serialized() {
+    use bitcoin::consensus::encode::serialize;
+    use bitcoin::{PackedLockTime, Transaction};
+    use bitcoin_script::Serialized;
+
+    let tx = Transaction {
+        version: 2,
+        lock_time: PackedLockTime(0),
+        input: vec![],
+        output: vec![],
+    };
+    let script = bitcoin_script!(<Serialized(&tx)> OP_SHA256);
+
+    let bytes = serialize(&tx);
+    assert_eq!(script, bitcoin_script!(<bytes> OP_SHA256));
+}
+
+#[cfg(feature = "introspection")]
+#[test]
+fn push_txout() {
+    use bitcoin::{Script, TxOut};
+
+    let txout = TxOut {
+        value: 1000,
+        script_pubkey: Script::from(vec![0x51]),
+    };
+    let script = bitcoin_script!(<txout>);
+
+    assert_eq!(
+        script.to_bytes(),
+        vec![10, 0xe8, 0x03, 0, 0, 0, 0, 0, 0, 1, 0x51]
+    );
+}
+
+#[test]
+fn lint_directive() {
+    let script = bitcoin_script! {
+        @lint(unterminated);
+        OP_DUP OP_HASH160 0x0102 OP_EQUALVERIFY OP_CHECKSIG
+    };
+
+    assert_eq!(script.to_bytes(), vec![118, 169, 2, 1, 2, 136, 172]);
+}
+
+#[test]
+fn byte_builtin() {
+    let version = 7u32;
+    let script = bitcoin_script!(<@byte(5)> <5> <@byte(version)>);
+
+    assert_eq!(script.to_bytes(), vec![1, 5, 85, 1, 7]);
+
+    let script = bitcoin_script!(<@byte(0x05)> <@byte(5u8)> <@byte(0b101)>);
+    assert_eq!(script.to_bytes(), vec![1, 5, 1, 5, 1, 5]);
+}
+
+#[test]
+fn builtin_names_without_prefix() {
+    fn byte(n: u8) -> Vec<u8> {
+        vec![n, n]
+    }
+
+    let script = bitcoin_script!(<byte(5)>);
+
+    assert_eq!(script.to_bytes(), vec![2, 5, 5]);
+}
+
+#[test]
+#[should_panic(expected = "byte value out of range")]
+fn byte_builtin_out_of_range() {
+    let version = 300;
+    let _ = bitcoin_script!(<@byte(version)>);
+}
+
+#[test]
+fn then_builtin() {
+    let pubkey = vec![2; 33];
+    let script = bitcoin_script!(<@then(pubkey, [OP_DUP, OP_HASH160])>);
+
+    let mut expected = vec![33];
+    expected.extend(&[2; 33]);
+    expected.extend(&[118, 169]);
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn eq_builtin() {
+    let hash = vec![0xab; 20];
+    let script = bitcoin_script!(OP_HASH160 <@eq(hash.clone())>);
+    let mut expected = vec![169, 20];
+    expected.extend(&hash);
+    expected.push(135);
+    assert_eq!(script.to_bytes(), expected);
+
+    let script = bitcoin_script!(OP_SIZE <@eqverify(32)> OP_SHA256);
+    assert_eq!(script.to_bytes(), vec![130, 1, 32, 136, 168]);
+}
+
+#[test]
+fn big_endian() {
+    let height: i64 = 840_000;
+    let flag: i64 = 1;
+    let bytes = vec![0xab, 0xcd];
+    let script = bitcoin_script! {
+        @be;
+        <height> OP_GREATERTHANOREQUAL OP_VERIFY
+        <flag> 0x1234 <bytes> 1000 -1
+    };
+
+    assert_eq!(
+        script.to_bytes(),
+        vec![
+            8, 0, 0, 0, 0, 0, 0x0c, 0xd1, 0x40, 162, 105, 81, 2, 0x12, 0x34, 2, 0xab, 0xcd, 8, 0,
+            0, 0, 0, 0, 0, 0x03, 0xe8, 79
+        ]
+    );
+}
+
+#[test]
+fn push_bytes() {
+    let script = bitcoin_script!(OP_PUSHBYTES_1 0x05 OP_PUSHBYTES_3 0xabcdef OP_DROP);
+    assert_eq!(script.to_bytes(), vec![1, 5, 3, 0xab, 0xcd, 0xef, 117]);
+
+    // only a hex literal is taken as the opcode's data
+    let x = 7;
+    let script = bitcoin_script!(OP_PUSHBYTES_1 5 OP_PUSHBYTES_1 <x> raw 0x02 0x0102);
+    assert_eq!(script.to_bytes(), vec![1, 0x55, 1, 0x57, 2, 2, 1, 2]);
+}
+
+#[test]
+fn push_bytes_disassembly() {
+    // a P2WSH output as printed by `decodescript`
+    let script = bitcoin_script!(
+        OP_0 OP_PUSHBYTES_32 0x1863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262
+    );
+
+    assert!(script.is_v0_p2wsh());
+    assert_eq!(script.len(), 34);
+}
+
+#[test]
+fn skip() {
+    let script = bitcoin_script! {
+        OP_DUP
+        #[skip] {
+            OP_HASH160 <undefined_variable> OP_EQUALVERIFY
+        }
+        OP_CHECKSIG
+    };
+
+    assert_eq!(script.to_bytes(), vec![118, 172]);
+}
+
+#[test]
+fn opcode_aliases() {
+    assert_eq!(
+        bitcoin_script!(OP_FALSE OP_TRUE OP_0 OP_CHECKLOCKTIMEVERIFY OP_CHECKSEQUENCEVERIFY),
+        bitcoin_script!(0 1 0 OP_CLTV OP_CSV)
+    );
+}
+
+#[test]
+fn opcode_name_forms() {
+    assert_eq!(
+        bitcoin_script!(dup hash160 0x0011223344556677889900112233445566778899 EqualVerify CHECKSIG),
+        bitcoin_script!(OP_DUP OP_HASH160 0x0011223344556677889900112233445566778899 OP_EQUALVERIFY OP_CHECKSIG)
+    );
+}
+
+#[test]
+fn unknown_opcode() {
+    let script = bitcoin_script!(OP_UNKNOWN(0xba) OP_UNKNOWN(0x50) OP_VERIFY);
+
+    assert_eq!(script.to_bytes(), vec![0xba, 0x50, 0x69]);
+}
+
+#[test]
+fn raw_bytes() {
+    let fragment = bitcoin_script!(OP_DUP OP_HASH160);
+    let x = 1;
+
+    let script = bitcoin_script!(raw 0x76a9 <x> raw 0x4c0100);
+    assert_eq!(script.to_bytes(), vec![0x76, 0xa9, 0x51, 0x4c, 0x01, 0x00]);
+    assert_eq!(
+        bitcoin_script!(raw 0x76a9 OP_DROP),
+        bitcoin_script!(<fragment> OP_DROP)
+    );
+}
+
+#[test]
+fn forced_pushdata() {
+    use bitcoin::blockdata::script::Instruction;
+
+    let script = bitcoin_script!(pushdata1(0x0102) pushdata2("ord") pushdata4(""));
+    assert_eq!(
+        script.to_bytes(),
+        vec![0x4c, 2, 1, 2, 0x4d, 3, 0, b'o', b'r', b'd', 0x4e, 0, 0, 0, 0]
+    );
+
+    // the pushes still decode to the same data
+    let pushes: Vec<Vec<u8>> = script
+        .instructions()
+        .map(|instruction| match instruction.unwrap() {
+            Instruction::PushBytes(bytes) => bytes.to_vec(),
+            _ => panic!("expected a push"),
+        })
+        .collect();
+    assert_eq!(pushes, vec![vec![1, 2], b"ord".to_vec(), vec![]]);
+}
+
+#[test]
+fn pushnum() {
+    let value: i64 = -300;
+
+    let script = bitcoin_script!(<@pushnum(255, 4)> <@pushnum(value, 3)> <@pushnum(0, 1)>);
+    assert_eq!(
+        script.to_bytes(),
+        vec![4, 0xff, 0, 0, 0, 3, 0x2c, 0x01, 0x80, 1, 0]
+    );
+}
+
+#[test]
+fn pushnum_constant_matches_runtime() {
+    // constants are encoded by the macro, anything else by `ScriptNum`
+    let values: [i64; 5] = [0x7f, 0x80, -0x80, 0xff, i32::MIN as i64 + 1];
+    let constants = [
+        bitcoin_script!(<@pushnum(127, 4)>),
+        bitcoin_script!(<@pushnum(128, 4)>),
+        bitcoin_script!(<@pushnum(-128, 4)>),
+        bitcoin_script!(<@pushnum(255, 4)>),
+        bitcoin_script!(<@pushnum(-2147483647, 4)>),
+    ];
+
+    for (value, constant) in values.iter().zip(constants) {
+        assert_eq!(bitcoin_script!(<@pushnum(*value, 4)>), constant);
+    }
+}
+
+#[test]
+#[should_panic(expected = "value doesn't fit in the pushnum width")]
+fn pushnum_overflow() {
+    let value: i64 = 128;
+    let _ = bitcoin_script!(<@pushnum(value, 1)>);
+}
+
+#[test]
+fn little_endian() {
+    let version: u32 = 2;
+    let amount: u64 = 50_000;
+
+    let script = bitcoin_script!(<@le32(version)> <@le64(amount)> <@le32(0)>);
+    assert_eq!(
+        script,
+        bitcoin_script!(0x02000000 0x50c3000000000000 0x00000000)
+    );
+}
+
+#[test]
+fn scriptnum() {
+    let timeout: i64 = 1 << 35;
+    let small: i64 = 5;
+
+    let script = bitcoin_script!(<@scriptnum(small)> <@scriptnum(timeout, 5)> <@scriptnum(-1)>);
+    assert_eq!(script.to_bytes(), vec![1, 5, 5, 0, 0, 0, 0, 0x08, 1, 0x81]);
+}
+
+#[test]
+fn scriptnum_constant_matches_runtime() {
+    // constants are encoded by the macro, anything else by `ScriptNum`
+    let values: [i64; 5] = [0x7f, 0x80, -0x80, 0xff, i32::MIN as i64 + 1];
+    let constants = [
+        bitcoin_script!(<@scriptnum(127)>),
+        bitcoin_script!(<@scriptnum(128)>),
+        bitcoin_script!(<@scriptnum(-128)>),
+        bitcoin_script!(<@scriptnum(255)>),
+        bitcoin_script!(<@scriptnum(-2147483647)>),
+    ];
+
+    for (value, constant) in values.iter().zip(constants) {
+        assert_eq!(bitcoin_script!(<@scriptnum(*value)>), constant);
+    }
+}
+
+#[test]
+#[should_panic(expected = "value doesn't fit in a 4-byte script number")]
+fn scriptnum_overflow() {
+    let timeout: i64 = 1 << 35;
+    let _ = bitcoin_script!(<@scriptnum(timeout, 4)>);
+}
+
+#[test]
+fn comments() {
+    let script = bitcoin_script! {
+        OP_DUP // line comment
+        /* block comment */ OP_HASH160
+        /// doc comment
+        OP_CHECKSIG
+        /** block doc comment */
+    };
+
+    assert_eq!(script.to_bytes(), vec![118, 169, 172]);
+}
+
+#[test]
+fn guarded() {
+    let enabled: i64 = 1;
+    let script = guarded!(<enabled> => { OP_DUP OP_HASH160 });
+    assert_eq!(script.to_bytes(), vec![81, 99, 118, 169, 104]);
+
+    let script = guarded!({ OP_DROP });
+    assert_eq!(script.to_bytes(), vec![99, 117, 104]);
+}
+
+#[test]
+fn addr_builtin() {
+    let expected = vec![
+        0x00, 0x14, 0x75, 0x1e, 0x76, 0xe8, 0x19, 0x91, 0x96, 0xd4, 0x54, 0x94, 0x1c, 0x45, 0xd1,
+        0xb3, 0xa3, 0x23, 0xf1, 0x43, 0x3b, 0xd6,
+    ];
+
+    let script = bitcoin_script!(<@addr("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")>);
+    assert_eq!(script.to_bytes(), expected);
+
+    let address = String::from("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+    let script = bitcoin_script!(<@addr(address.as_str())>);
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn hygiene() {
+    // none of these may resolve to bindings in the generated code
+    let builder = vec![1];
+    let value = vec![2];
+    let push = vec![3];
+    let script = vec![4];
+    let trace = vec![5];
+
+    let result = bitcoin_script!(<builder> <value> <push> <script.clone()> <trace.clone()>);
+    assert_eq!(result.to_bytes(), vec![1, 1, 1, 2, 1, 3, 1, 4, 1, 5]);
+
+    let result = bitcoin_script_traced!(|_: &[u8]| {}; <script> <trace>);
+    assert_eq!(result.to_bytes(), vec![1, 4, 1, 5]);
+}
+
+#[test]
+fn checksum() {
+    use bitcoin::hashes::{sha256d, Hash};
+
+    let script = bitcoin_script!(@checksum(sha256d, 4); OP_RETURN 0x01020304);
+    let body = bitcoin_script!(OP_RETURN 0x01020304).to_bytes();
+    let hash = sha256d::Hash::hash(&body).into_inner();
+    let mut expected = body.clone();
+    expected.push(4);
+    expected.extend(&hash[..4]);
+    assert_eq!(script.to_bytes(), expected);
+
+    // computed at runtime when the script has escapes
+    let data = vec![1, 2, 3, 4];
+    let script = bitcoin_script!(@checksum(sha256d, 4); OP_RETURN <data>);
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn instructions_builtin() {
+    let raw = vec![0x76, 0xa9];
+    let script = bitcoin_script!(<@instructions(raw.as_slice())> <raw.clone()>);
+    assert_eq!(script.to_bytes(), vec![0x76, 0xa9, 2, 0x76, 0xa9]);
+
+    let script = bitcoin_script!(<@instructions(&raw)> <@instructions([0x87])>);
+    assert_eq!(script.to_bytes(), vec![0x76, 0xa9, 0x87]);
+}
+
+#[test]
+fn try_script() {
+    fn push_hex(hex: &str) -> Result<bitcoin::Script, std::num::ParseIntError> {
+        try_bitcoin_script!(<i64::from_str_radix(hex, 16)?> OP_ADD)
+    }
+
+    assert_eq!(push_hex("ff").unwrap().to_bytes(), vec![2, 0xff, 0, 0x93]);
+    assert!(push_hex("zz").is_err());
+
+    let script: Result<bitcoin::Script, String> = try_bitcoin_script!(1);
+    assert_eq!(script.unwrap().to_bytes(), vec![0x51]);
+}
+
+#[test]
+fn try_script_result_escapes() {
+    use bitcoin::PublicKey;
+    use std::str::FromStr;
+
+    enum Error {
+        Key,
+        Int,
+    }
+    impl From<bitcoin::util::key::Error> for Error {
+        fn from(_: bitcoin::util::key::Error) -> Self {
+            Error::Key
+        }
+    }
+    impl From<std::num::ParseIntError> for Error {
+        fn from(_: std::num::ParseIntError) -> Self {
+            Error::Int
+        }
+    }
+
+    fn p2pk(key: &str, timeout: &str) -> Result<bitcoin::Script, Error> {
+        try_bitcoin_script!(
+            <i64::from_str(timeout)> OP_CSV OP_DROP
+            <PublicKey::from_str(key)> OP_CHECKSIG
+        )
+    }
+
+    let key = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    let script = p2pk(key, "144").ok().unwrap();
+    assert_eq!(&script.as_bytes()[..5], &[2, 0x90, 0, 0xb2, 0x75]);
+    assert_eq!(script.len(), 5 + 34 + 1);
+
+    assert!(matches!(p2pk(key, "soon"), Err(Error::Int)));
+    assert!(matches!(p2pk("not a key", "144"), Err(Error::Key)));
+}
+
+#[test]
+fn redeem_builtin() {
+    let redeem_script = bitcoin_script!(OP_PUSHNUM_2 OP_CHECKMULTISIG);
+    let script = bitcoin_script!(0 <@redeem(redeem_script)>);
+    assert_eq!(script.to_bytes(), vec![0, 2, 0x52, 0xae]);
+
+    let script = bitcoin_script!(<@redeem(&redeem_script)> <&redeem_script>);
+    assert_eq!(script.to_bytes(), vec![2, 0x52, 0xae, 0x52, 0xae]);
+
+    let script = bitcoin_script!(<@push_script(redeem_script)>);
+    assert_eq!(script.to_bytes(), vec![2, 0x52, 0xae]);
+}
+
+#[test]
+fn witness_program() {
+    let script = assert_witness_program!(
+        0xd8b64e841d8822393e8f4f78fb9a1196e45ab4325494a9522bf2a12b0699ab7e;
+        OP_DUP OP_HASH160 0x0102 OP_EQUALVERIFY OP_CHECKSIG
+    );
+    assert_eq!(
+        script.to_bytes(),
+        vec![0x76, 0xa9, 0x02, 0x01, 0x02, 0x88, 0xac]
+    );
+}
+
+#[test]
+fn witness_program_push() {
+    use bitcoin::util::address::WitnessVersion;
+    use bitcoin_script::WitnessProgram;
+
+    let witness_script = bitcoin_script!(OP_PUSHNUM_1);
+    let program =
+        WitnessProgram::new(WitnessVersion::V0, witness_script.wscript_hash().to_vec()).unwrap();
+    assert_eq!(
+        bitcoin_script!(0 <&program>),
+        bitcoin::Script::new_v0_p2wsh(&witness_script.wscript_hash())
+    );
+
+    let program = WitnessProgram::new(WitnessVersion::V1, vec![0x79; 32]).unwrap();
+    let script = bitcoin_script!(<program.version()> <program>);
+    assert!(script.is_v1_p2tr());
+}
+
+#[test]
+fn p2sh_p2wsh() {
+    let nested = p2sh_p2wsh_script!(OP_PUSHNUM_1);
+
+    assert_eq!(nested.witness_script.to_bytes(), vec![0x51]);
+    assert_eq!(
+        nested.redeem_script.to_bytes(),
+        bitcoin_script!(0 0x4ae81572f06e1b88fd5ced7a1a000945432e83e1551e6f721ee9c00b8cc33260)
+            .to_bytes()
+    );
+    assert_eq!(
+        nested.script_pubkey.to_bytes(),
+        bitcoin_script!(OP_HASH160 0x72c44f957fc011d97e3406667dca5b1c930c4026 OP_EQUAL).to_bytes()
+    );
+}
+
+#[test]
+fn impl_pushable() {
+    use bitcoin::blockdata::script::Builder;
+
+    // pushes nothing for `None`, so the script shape depends on the value
+    struct Preimage(Option<Vec<u8>>);
+
+    impl Pushable for Preimage {
+        fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+            match &self.0 {
+                Some(preimage) => builder.push_slice(preimage),
+                None => builder,
+            }
+        }
+    }
+
+    let script = bitcoin_script!(<Preimage(Some(vec![1, 2]))> OP_SHA256);
+    assert_eq!(script.to_bytes(), vec![2, 1, 2, 0xa8]);
+
+    let script = bitcoin_script!(<Preimage(None)> OP_SHA256);
+    assert_eq!(script.to_bytes(), vec![0xa8]);
+}
+
+#[test]
+fn impl_pushable_composes() {
+    use bitcoin::blockdata::script::Builder;
+
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    struct Commitment(u8);
+
+    impl Pushable for Commitment {
+        fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+            builder.push_slice(&[self.0; 2])
+        }
+    }
+
+    // user impls work wherever the built-in ones do
+    #[derive(Pushable)]
+    struct Commitments {
+        first: Commitment,
+        rest: Vec<u8>,
+    }
+
+    let commitment = Commitment(1);
+    let set: std::collections::BTreeSet<_> =
+        vec![Commitment(3), Commitment(2)].into_iter().collect();
+    let commitments = Commitments {
+        first: commitment,
+        rest: vec![4],
+    };
+
+    let script = bitcoin_script!(
+        <&commitment> <Some(commitment)> <set> <Each([commitment])> <commitments>
+    );
+    assert_eq!(
+        script.to_bytes(),
+        vec![2, 1, 1, 2, 1, 1, 2, 2, 2, 2, 3, 3, 2, 1, 1, 2, 1, 1, 1, 4]
+    );
+}
+
+#[test]
+fn derive_pushable() {
+    #[derive(Pushable)]
+    struct Lock {
+        hash: Vec<u8>,
+        timeout: i64,
+    }
+
+    #[derive(Pushable)]
+    struct Pair(Lock, i64);
+
+    let lock = Lock {
+        hash: vec![0xab, 0xcd],
+        timeout: 1000,
+    };
+    let script = bitcoin_script!(<lock> OP_CSV);
+    assert_eq!(script.to_bytes(), vec![2, 0xab, 0xcd, 2, 0xe8, 0x03, 0xb2]);
+
+    let pair = Pair(
+        Lock {
+            hash: vec![],
+            timeout: 1000,
+        },
+        5,
+    );
+    let script = bitcoin_script!(@be; <pair>);
+    assert_eq!(
+        script.to_bytes(),
+        vec![0, 8, 0, 0, 0, 0, 0, 0, 0x03, 0xe8, 0x55]
+    );
+}
+
+#[test]
+fn size_check() {
+    assert_eq!(size_check!(32).to_bytes(), vec![0x82, 0x01, 0x20, 0x88]);
+    assert_eq!(size_check!(0).to_bytes(), vec![0x82, 0x00, 0x88]);
+
+    let len = 520;
+    let script = bitcoin_script!(<size_check!(len)> OP_HASH160);
+    assert_eq!(script.to_bytes(), vec![0x82, 0x02, 0x08, 0x02, 0x88, 0xa9]);
+}
+
+#[test]
+fn witness_template() {
+    let pubkey = vec![0x02; 33];
+    let (script, witness) = script_with_witness_template! {
+        [_sig, pubkey];
+        OP_DUP OP_HASH160 0x0102 OP_EQUALVERIFY OP_CHECKSIG
+    };
+
+    assert_eq!(script.to_bytes(), vec![0x76, 0xa9, 2, 1, 2, 0x88, 0xac]);
+    assert_eq!(
+        witness,
+        vec![
+            WitnessItem::Placeholder("sig".to_string()),
+            WitnessItem::Bytes(pubkey),
+        ]
+    );
+}
+
+#[test]
+fn reversed() {
+    let foo = vec![1, 2, 3, 4];
+    let script = bitcoin_script_rev! {
+        OP_HASH160
+        1234
+        <foo>
+        OP_EQUAL
+    };
+
+    assert_eq!(script.to_bytes(), vec![135, 4, 1, 2, 3, 4, 2, 210, 4, 169]);
+}
+
+#[test]
+fn push_control_block() {
+    use bitcoin::hashes::hex::FromHex;
+    use bitcoin::util::taproot::ControlBlock;
+
+    // leaf version 0xc0 with an even output key, followed by the internal key
+    // and one merkle path node
+    let bytes = Vec::<u8>::from_hex(concat!(
+        "c0",
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        "0101010101010101010101010101010101010101010101010101010101010101",
+    ))
+    .unwrap();
+    let control_block = ControlBlock::from_slice(&bytes).unwrap();
+
+    let script = bitcoin_script!(<control_block>);
+
+    let mut expected = vec![65];
+    expected.extend(bytes);
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn push_control_block_from_spend_info() {
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::util::taproot::{LeafVersion, TaprootBuilder};
+    use bitcoin::XOnlyPublicKey;
+    use std::str::FromStr;
+
+    let secp = Secp256k1::verification_only();
+    let internal_key = XOnlyPublicKey::from_str(
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+    )
+    .unwrap();
+    let leaf = bitcoin_script!(OP_DROP 1);
+    let spend_info = TaprootBuilder::new()
+        .add_leaf(1, leaf.clone())
+        .unwrap()
+        .add_leaf(1, bitcoin_script!(OP_DROP 0))
+        .unwrap()
+        .finalize(&secp, internal_key)
+        .unwrap();
+    let control_block = spend_info
+        .control_block(&(leaf.clone(), LeafVersion::TapScript))
+        .unwrap();
+
+    // the tail of a script-path spend: the leaf script, then its control block
+    let script = bitcoin_script!(<leaf.as_bytes()> <&control_block>);
+
+    let mut expected = vec![leaf.len() as u8];
+    expected.extend(leaf.as_bytes());
+    expected.push(65);
+    expected.extend(control_block.serialize());
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn push_btree_set() {
+    use bitcoin::PublicKey;
+    use std::collections::BTreeSet;
+    use std::str::FromStr;
+
+    let a =
+        PublicKey::from_str("02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5")
+            .unwrap();
+    let b =
+        PublicKey::from_str("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+            .unwrap();
+    let keys: BTreeSet<_> = vec![a, b].into_iter().collect();
+
+    let script = bitcoin_script!(<keys> OP_PUSHNUM_2 OP_CHECKMULTISIG);
+    assert_eq!(
+        script,
+        bitcoin_script!(<b> <a> OP_PUSHNUM_2 OP_CHECKMULTISIG)
+    );
+
+    let ints: BTreeSet<i64> = vec![3, 1, 2].into_iter().collect();
+    assert_eq!(bitcoin_script!(<ints>).to_bytes(), vec![81, 82, 83]);
+}
+
+#[test]
+fn zero() {
+    use bitcoin::blockdata::opcodes::all::OP_PUSHBYTES_0;
+    use bitcoin::blockdata::script::Instruction;
+
+    let zero: i64 = 0;
+    let script = bitcoin_script!(0 <zero> <ScriptNum(0)>);
+    assert_eq!(script.to_bytes(), vec![OP_PUSHBYTES_0.to_u8(); 3]);
+
+    // OP_0 pushes an empty element rather than a zero byte
+    for instruction in script.instructions() {
+        assert_eq!(instruction, Ok(Instruction::PushBytes(&[])));
+    }
+}
+
+#[test]
+fn push_byte_array() {
+    let hash = [0xab; 32];
+    let script = bitcoin_script!(OP_SHA256 <hash> OP_EQUALVERIFY <&[1u8, 2]> <[0u8; 0]>);
+
+    let mut expected = vec![0xa8, 32];
+    expected.extend_from_slice(&hash);
+    expected.extend_from_slice(&[0x88, 2, 1, 2, 0]);
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn push_hash_types() {
+    use bitcoin::hashes::Hash;
+    use bitcoin::PublicKey;
+    use std::str::FromStr;
+
+    let pubkey =
+        PublicKey::from_str("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+            .unwrap();
+    let script = bitcoin_script!(OP_HASH160 <pubkey.pubkey_hash()> OP_EQUALVERIFY);
+    let expected =
+        bitcoin_script!(OP_HASH160 0x751e76e8199196d454941c45d1b3a323f1433bd6 OP_EQUALVERIFY);
+    assert_eq!(script, expected);
+
+    let redeem_script = bitcoin_script!(OP_PUSHNUM_1);
+    let script = bitcoin_script!(
+        <redeem_script.script_hash()>
+        <pubkey.wpubkey_hash().unwrap()>
+        <redeem_script.wscript_hash()>
+    );
+    let mut expected = vec![20];
+    expected.extend_from_slice(&redeem_script.script_hash().into_inner());
+    expected.push(20);
+    expected.extend_from_slice(&pubkey.wpubkey_hash().unwrap().into_inner());
+    expected.push(32);
+    expected.extend_from_slice(&redeem_script.wscript_hash().into_inner());
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn push_txid() {
+    use bitcoin::Txid;
+    use std::str::FromStr;
+
+    // displayed (and parsed) in the reverse of its internal order
+    let txid =
+        Txid::from_str("0000000000000000000000000000000000000000000000000000000000000001").unwrap();
+    let script = bitcoin_script!(<txid>);
+    let mut expected = vec![32, 1];
+    expected.extend_from_slice(&[0; 31]);
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn push_hashes() {
+    use bitcoin::hashes::{hash160, ripemd160, sha256, sha256d, Hash};
+
+    let preimage = [1, 2, 3];
+    let digest = sha256::Hash::hash(&preimage);
+    let script = bitcoin_script!(OP_SHA256 <digest> OP_EQUAL);
+    let expected = bitcoin_script!(
+        OP_SHA256 0x039058c6f2c0cb492c533b0a4d14ef77cc0f78abccced5287d84a1a2011cfb81 OP_EQUAL
+    );
+    assert_eq!(script, expected);
+
+    let script = bitcoin_script!(
+        <sha256d::Hash::hash(&preimage)>
+        <ripemd160::Hash::hash(&preimage)>
+        <hash160::Hash::hash(&preimage)>
+    );
+    let mut expected = vec![32];
+    expected.extend_from_slice(&sha256d::Hash::hash(&preimage).into_inner());
+    expected.push(20);
+    expected.extend_from_slice(&ripemd160::Hash::hash(&preimage).into_inner());
+    expected.push(20);
+    expected.extend_from_slice(&hash160::Hash::hash(&preimage).into_inner());
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn push_taproot_hashes() {
+    use bitcoin::hashes::{sha256, Hash};
+    use bitcoin::util::taproot::{LeafVersion, TapBranchHash, TapLeafHash, TapTweakHash};
+    use bitcoin::XOnlyPublicKey;
+    use std::str::FromStr;
+
+    let leaf = TapLeafHash::from_script(&bitcoin_script!(OP_PUSHNUM_1), LeafVersion::TapScript);
+    let branch = TapBranchHash::from_node_hashes(
+        sha256::Hash::from_inner(leaf.into_inner()),
+        sha256::Hash::from_inner([0; 32]),
+    );
+    let key = XOnlyPublicKey::from_str(
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+    )
+    .unwrap();
+    let tweak = TapTweakHash::from_key_and_tweak(key, Some(branch));
+
+    let script = bitcoin_script!(<leaf> <branch> <tweak>);
+    let mut expected = vec![];
+    for hash in [leaf.into_inner(), branch.into_inner(), tweak.into_inner()] {
+        expected.push(32);
+        expected.extend_from_slice(&hash);
+    }
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn push_secp256k1_key() {
+    use bitcoin::secp256k1::PublicKey;
+    use std::str::FromStr;
+
+    let key =
+        PublicKey::from_str("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+            .unwrap();
+    let script = bitcoin_script!(<key> OP_CHECKSIG);
+
+    let expected = bitcoin_script!(<bitcoin::PublicKey::new(key)> OP_CHECKSIG);
+    assert_eq!(script, expected);
+    assert_eq!(script.len(), 35);
+}
+
+#[test]
+fn push_xonly_key() {
+    use bitcoin::XOnlyPublicKey;
+    use std::str::FromStr;
+
+    let key = XOnlyPublicKey::from_str(
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+    )
+    .unwrap();
+    let script = bitcoin_script!(<key> OP_CHECKSIG);
+
+    let mut expected = vec![32];
+    expected.extend_from_slice(&key.serialize());
+    expected.push(0xac);
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn push_sequence() {
+    use bitcoin::Sequence;
+
+    let script = bitcoin_script!(<Sequence::from_height(144)> OP_CSV);
+    assert_eq!(script.to_bytes(), vec![2, 0x90, 0, 0xb2]);
+
+    // relative time locks have bit 22 set
+    let script = bitcoin_script!(<Sequence::from_512_second_intervals(2)> <Sequence::MAX>);
+    assert_eq!(
+        script.to_bytes(),
+        vec![3, 2, 0, 0x40, 5, 0xff, 0xff, 0xff, 0xff, 0]
+    );
+
+    // big-endian like any other integer escape
+    assert_eq!(
+        bitcoin_script!(@be; <Sequence::from_height(1000)>),
+        bitcoin_script!(@be; <1000i64>)
+    );
+}
+
+#[test]
+fn push_relative_lock_time() {
+    let script = bitcoin_script!(<RelativeLockTime::Blocks(144)> OP_CSV OP_DROP);
+    assert_eq!(script.to_bytes(), vec![2, 0x90, 0, 0xb2, 0x75]);
+
+    // the type flag is bit 22
+    let script = bitcoin_script!(<RelativeLockTime::Time(2)> OP_CSV);
+    assert_eq!(script.to_bytes(), vec![3, 2, 0, 0x40, 0xb2]);
+
+    assert_eq!(
+        bitcoin_script!(@be; <RelativeLockTime::Blocks(1000)>),
+        bitcoin_script!(@be; <1000i64>)
+    );
+}
+
+#[test]
+fn push_lock_time() {
+    use bitcoin::{LockTime, PackedLockTime};
+
+    let height = LockTime::from_height(700_000).unwrap();
+    let script = bitcoin_script!(<height> OP_CLTV);
+    assert_eq!(script.to_bytes(), vec![3, 0x60, 0xae, 0x0a, 0xb1]);
+
+    let time = LockTime::from_time(1_700_000_000).unwrap();
+    let script = bitcoin_script!(<time> <PackedLockTime::from(time)>);
+    assert_eq!(
+        script.to_bytes(),
+        vec![4, 0x00, 0xf1, 0x53, 0x65, 4, 0x00, 0xf1, 0x53, 0x65]
+    );
+
+    // big-endian like any other integer escape
+    assert_eq!(
+        bitcoin_script!(@be; <height> <PackedLockTime::from(time)>),
+        bitcoin_script!(@be; <700_000i64> <1_700_000_000i64>)
+    );
+}
+
+#[test]
+fn push_small_ints() {
+    let (a, b, c): (u8, u16, u32) = (5, 1000, u32::MAX);
+    let script = bitcoin_script!(<a> <b> <c> <-1i8> <2016 * 5>);
+    assert_eq!(
+        script.to_bytes(),
+        vec![0x55, 2, 0xe8, 0x03, 5, 0xff, 0xff, 0xff, 0xff, 0, 0x4f, 2, 0x60, 0x27]
+    );
+
+    let script = bitcoin_script!(@be; <b> <a>);
+    assert_eq!(
+        script.to_bytes(),
+        vec![8, 0, 0, 0, 0, 0, 0, 0x03, 0xe8, 0x55]
+    );
+}
+
+#[test]
+fn push_checked_ints() {
+    let fee: u64 = 100_000;
+    let len = vec![0; 32].len();
+    let script = bitcoin_script!(<fee> <len>);
+    assert_eq!(script.to_bytes(), vec![3, 0xa0, 0x86, 0x01, 1, 32]);
+}
+
+#[test]
+#[should_panic(expected = "is out of range for a script number")]
+fn push_checked_ints_overflow() {
+    let _ = bitcoin_script!(<u64::MAX>);
+}
+
+#[test]
+fn push_option() {
+    let annex: Option<Vec<u8>> = Some(vec![0x50, 1]);
+    let missing: Option<Vec<u8>> = None;
+    let script = bitcoin_script!(<annex> <missing> OP_DROP);
+    assert_eq!(script.to_bytes(), vec![2, 0x50, 1, 0x75]);
+}
+
+#[test]
+fn push_each() {
+    use bitcoin::XOnlyPublicKey;
+    use std::str::FromStr;
+
+    let proof = [[0xaa; 32], [0xbb; 32]];
+    let script = bitcoin_script!(<Each(proof.iter().rev())> <Each(&[1i64, 1000])>);
+    let mut expected = vec![32];
+    expected.extend_from_slice(&[0xbb; 32]);
+    expected.push(32);
+    expected.extend_from_slice(&[0xaa; 32]);
+    expected.extend_from_slice(&[0x51, 2, 0xe8, 0x03]);
+    assert_eq!(script.to_bytes(), expected);
+
+    let key = XOnlyPublicKey::from_str(
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+    )
+    .unwrap();
+    let keys = vec![key, key];
+    let script = bitcoin_script!(<Each(&keys)>);
+    assert_eq!(script.len(), 66);
+    let script = bitcoin_script!(<Each(Vec::<Vec<u8>>::new())>);
+    assert_eq!(script.to_bytes(), vec![]);
+}
+
+#[test]
+fn push_text() {
+    let content_type = String::from("text/plain");
+    let script = bitcoin_script!(0 OP_IF <"ord"> <content_type> <"hi"> OP_ENDIF);
+
+    let mut expected = vec![0, 0x63, 3];
+    expected.extend_from_slice(b"ord");
+    expected.push(10);
+    expected.extend_from_slice(b"text/plain");
+    expected.push(2);
+    expected.extend_from_slice(b"hi");
+    expected.push(0x68);
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn push_byte_vecs() {
+    let preimages = vec![vec![0xaa; 32], vec![0xbb; 32]];
+
+    let script = bitcoin_script!(<Each(&preimages)> <Each(&preimages[..1])>);
+    assert_eq!(
+        script,
+        bitcoin_script!(<&preimages[0]> <&preimages[1]> <&preimages[0]>)
+    );
+}
+
+#[test]
+fn push_unit() {
+    use std::cell::Cell;
+
+    let built = Cell::new(0);
+    let count =
+        |debug: bool| bitcoin_script!(OP_DUP { if debug { built.set(built.get() + 1) } } OP_DROP);
+
+    assert_eq!(count(true), bitcoin_script!(OP_DUP OP_DROP));
+    assert_eq!(count(false), bitcoin_script!(OP_DUP OP_DROP));
+    assert_eq!(built.get(), 1);
+    assert_eq!(bitcoin_script!(<()> 1).to_bytes(), vec![0x51]);
+}
+
+#[test]
+fn push_tuple() {
+    let sig = vec![0x30, 0x44];
+
+    fn spend(sig: &[u8], n: i64) -> (&[u8], i64) {
+        (sig, n)
+    }
+
+    let script = bitcoin_script!(<spend(&sig, 2)> <(1, &sig, 3)>);
+    assert_eq!(
+        script.to_bytes(),
+        vec![2, 0x30, 0x44, 0x52, 0x51, 2, 0x30, 0x44, 0x53]
+    );
+}
+
+#[test]
+fn push_instructions() {
+    use bitcoin::blockdata::script::Instruction;
+
+    let parsed = bitcoin_script!(OP_DUP OP_HASH160 0x0102 OP_EQUALVERIFY);
+    let instructions = parsed
+        .instructions()
+        .collect::<Result<Vec<Instruction>, _>>()
+        .unwrap();
+
+    let script = bitcoin_script!(<Each(&instructions)> OP_CHECKSIG);
+    assert_eq!(
+        script,
+        bitcoin_script!(OP_DUP OP_HASH160 0x0102 OP_EQUALVERIFY OP_CHECKSIG)
+    );
+
+    let script = bitcoin_script!(<&instructions[2]> <Each(instructions.iter().skip(3))>);
+    assert_eq!(script, bitcoin_script!(0x0102 OP_EQUALVERIFY));
+}
+
+#[test]
+fn string_literals() {
+    let body = "Hello, world!";
+
+    let script = bitcoin_script!(
+        0 OP_IF "ord" 1 "text/plain;charset=utf-8" 0 <body> OP_ENDIF
+    );
+    assert_eq!(
+        script,
+        bitcoin_script!(
+            0 OP_IF 0x6f7264 1 <"text/plain;charset=utf-8"> 0 <body> OP_ENDIF
+        )
+    );
+    assert_eq!(
+        bitcoin_script!("caf\u{e9}" r"\n").to_bytes(),
+        vec![5, b'c', b'a', b'f', 0xc3, 0xa9, 2, b'\\', b'n']
+    );
+}
+
+#[test]
+fn byte_string_literals() {
+    assert_eq!(
+        bitcoin_script!(b"\x01\x02abc" OP_DROP),
+        bitcoin_script!(0x0102616263 OP_DROP)
+    );
+    assert_eq!(
+        bitcoin_script!(br"\x01").to_bytes(),
+        vec![4, b'\\', b'x', b'0', b'1']
+    );
+}
+
+#[test]
+fn hex_separators() {
+    assert_eq!(
+        bitcoin_script!(0x0102_0304 OP_PUSHBYTES_4 0xdead_beef),
+        bitcoin_script!(0x01020304 0xdeadbeef)
+    );
+}
+
+#[test]
+fn int_separators() {
+    assert_eq!(
+        bitcoin_script!(52_560 OP_CSV -1_000 <1_000 * 2>),
+        bitcoin_script!(52560 OP_CSV -1000 2000)
+    );
+}
+
+#[test]
+fn escape_generics() {
+    let x = 1 << 12;
+    let (a, b) = (3, 2);
+
+    let script = bitcoin_script!(<Vec::<u8>::new()> <x >> 8> <None::<Vec<u8>>> <(a < b)> <a >= b>);
+    assert_eq!(script.to_bytes(), vec![0, 0x60, 0, 0x51]);
+
+    let script = bitcoin_script!(<vec![1u8]: Vec<u8>> <x as u16 >> 4: u16>);
+    assert_eq!(script.to_bytes(), vec![1, 1, 2, 0, 1]);
+}
+
+#[test]
+fn escape_block() {
+    let (a, b) = (3i64, 20i64);
+
+    let script = bitcoin_script!(<{ if a > b { a } else { b } }> <{ a < b && b >> 2 > a }> OP_MAX);
+    assert_eq!(script.to_bytes(), vec![1, 20, 0x51, 0xa4]);
+}
+
+#[test]
+fn brace_escape() {
+    let (a, b) = (3i64, 20i64);
+    let bytes = vec![1u8, 2];
+
+    let script = bitcoin_script!({ a < b } OP_VERIFY { &bytes } { Vec::<u8>::new() } { @byte(7) });
+    assert_eq!(script, bitcoin_script!(1 OP_VERIFY 0x0102 0 <@byte(7)>));
+
+    let template = script_template!({ _n: i64 } OP_ADD { _n });
+    assert_eq!(template(5), bitcoin_script!(5 OP_ADD 5));
+}
+
+#[test]
+fn dollar_escape() {
+    let pubkey_hash = [0xab; 20];
+    let timeout = 144;
+
+    let script = bitcoin_script!($timeout OP_CSV OP_DROP OP_HASH160 $pubkey_hash OP_EQUALVERIFY);
+    assert_eq!(
+        script,
+        bitcoin_script!(<timeout> OP_CSV OP_DROP OP_HASH160 <pubkey_hash> OP_EQUALVERIFY)
+    );
+
+    let template = script_template!($_n OP_ADD);
+    assert_eq!(template(2i64), bitcoin_script!(2 OP_ADD));
+}
+
+#[test]
+fn escape_ascription() {
+    let bytes = [1u8, 2, 3];
+
+    let script = bitcoin_script!(<1 << 20: i64> <&bytes[1..]: &[u8]> <u8::MAX: u8>);
+    assert_eq!(script.to_bytes(), vec![3, 0, 0, 0x10, 2, 2, 3, 2, 0xff, 0]);
+}
+
+#[test]
+fn push_fragment() {
+    use bitcoin::blockdata::opcodes::all::*;
+    use bitcoin::blockdata::script::Builder;
+
+    fn check_sig(key_index: i64) -> ScriptFragment<impl Fn(Builder) -> Builder> {
+        ScriptFragment(move |builder: Builder| {
+            builder
+                .push_int(key_index)
+                .push_opcode(OP_PICK)
+                .push_opcode(OP_CHECKSIGVERIFY)
+        })
+    }
+
+    let script = bitcoin_script!(<check_sig(1)> <check_sig(2)> 1);
+    assert_eq!(
+        script,
+        bitcoin_script!(1 OP_PICK OP_CHECKSIGVERIFY 2 OP_PICK OP_CHECKSIGVERIFY 1)
+    );
+}
+
+#[test]
+fn push_opcode() {
+    use bitcoin::blockdata::opcodes::{all::*, All};
+
+    fn timelock(relative: bool, value: i64) -> bitcoin::Script {
+        let opcode: All = if relative { OP_CSV } else { OP_CLTV };
+        bitcoin_script!(<value> <opcode> OP_DROP)
+    }
+
+    assert_eq!(timelock(true, 144).to_bytes(), vec![2, 0x90, 0, 0xb2, 0x75]);
+    assert_eq!(
+        timelock(false, 144).to_bytes(),
+        vec![2, 0x90, 0, 0xb1, 0x75]
+    );
+}
+
+#[test]
+fn push_bool() {
+    let cooperative = true;
+    let script = bitcoin_script!(<cooperative> <!cooperative> OP_BOOLAND);
+    assert_eq!(script.to_bytes(), vec![0x51, 0x00, 0x9a]);
+}
+
+#[test]
+fn push_range() {
+    let range = 5..10;
+    let script = bitcoin_script!(<range> OP_WITHIN);
+    assert_eq!(script.to_bytes(), vec![85, 90, 165]);
+
+    let script = bitcoin_script!(<-1000..1000>);
+    assert_eq!(script.to_bytes(), vec![2, 0xe8, 0x83, 2, 0xe8, 0x03]);
+}
+
+#[test]
+fn push_script() {
+    let branch_a = bitcoin_script!(OP_DUP OP_HASH160 0x0102 OP_EQUALVERIFY);
+    // non-minimal encodings have to be kept as they are
+    let branch_b = bitcoin::Script::from(vec![0x01, 0x05, 0x4c, 0x01, 0xff, 0x75]);
+    let empty = bitcoin::Script::new();
+
+    let script = bitcoin_script!(<branch_a.clone()> <&branch_b> <empty>);
+    let mut expected = branch_a.to_bytes();
+    expected.extend(branch_b.to_bytes());
+    assert_eq!(script.to_bytes(), expected);
+
+    let script = bitcoin_script!(OP_IF <branch_a.clone()> OP_ELSE <&branch_b> OP_ENDIF);
+    let mut expected = vec![99];
+    expected.extend(branch_a.to_bytes());
+    expected.push(103);
+    expected.extend(branch_b.to_bytes());
+    expected.push(104);
+    assert_eq!(script.to_bytes(), expected);
+
+    // escapes only borrow, so a fragment can be spliced in more than once
+    let prefix_script = bitcoin_script!(OP_HASH160 0x0102 OP_EQUALVERIFY);
+    let script = bitcoin_script!(OP_DUP <prefix_script> OP_CHECKSIG <prefix_script>);
+    let mut expected = vec![0x76];
+    expected.extend(prefix_script.to_bytes());
+    expected.push(0xac);
+    expected.extend(prefix_script.to_bytes());
+    assert_eq!(script.to_bytes(), expected);
+}
+
+#[test]
+fn push_signatures() {
+    use bitcoin::secp256k1::{Message, Secp256k1, SecretKey};
+    use bitcoin::{EcdsaSig, EcdsaSighashType, SchnorrSig};
+
+    let secp = Secp256k1::signing_only();
+    let message = Message::from_slice(&[0xab; 32]).unwrap();
+    let sigs: Vec<EcdsaSig> = vec![1, 2]
+        .into_iter()
+        .map(|i| {
+            let key = SecretKey::from_slice(&[i; 32]).unwrap();
+            EcdsaSig::sighash_all(secp.sign_ecdsa(&message, &key))
+        })
+        .collect();
+
+    let script = bitcoin_script!(0 <Each(&sigs)>);
+    let mut expected = bitcoin::blockdata::script::Builder::new().push_int(0);
+    for sig in &sigs {
+        expected = expected.push_slice(&sig.to_vec());
+    }
+    assert_eq!(script, expected.into_script());
+
+    let sig = sigs[0];
+    let script = bitcoin_script!(<sig> <(sig.sig, EcdsaSighashType::Single)>);
+    let mut single = sig.sig.serialize_der().to_vec();
+    single.push(0x03);
+    let expected = bitcoin::blockdata::script::Builder::new()
+        .push_slice(&sig.to_vec())
+        .push_slice(&single)
+        .into_script();
+    assert_eq!(script, expected);
+
+    // SIGHASH_DEFAULT has no sighash byte, SIGHASH_ALL does
+    let mut sighash_all = vec![0x22; 64];
+    sighash_all.push(0x01);
+    let sigs = vec![
+        SchnorrSig::from_slice(&[0x11; 64]).unwrap(),
+        SchnorrSig::from_slice(&sighash_all).unwrap(),
+    ];
+    let script = bitcoin_script!(<Each(&sigs)>);
+    let bytes = script.to_bytes();
+    assert_eq!(bytes.len(), 1 + 64 + 1 + 65);
+    assert_eq!(bytes[0], 64);
+    assert_eq!(bytes[65], 65);
+    assert_eq!(bytes[130], 0x01);
+
+    let script = bitcoin_script!(<sigs[0]> <sigs[1]>);
+    assert_eq!(script.to_bytes(), bytes);
+}
+
+#[test]
+fn script_num() {
+    for (n, minimal, data) in [
+        (0, vec![0x00], vec![0x00]),
+        (1, vec![0x51], vec![0x01, 0x01]),
+        (16, vec![0x60], vec![0x01, 0x10]),
+        (-1, vec![0x4f], vec![0x01, 0x81]),
+        (-5, vec![0x01, 0x85], vec![0x01, 0x85]),
+        (255, vec![0x02, 0xff, 0x00], vec![0x02, 0xff, 0x00]),
+    ] {
+        assert_eq!(bitcoin_script!(<n>).to_bytes(), minimal);
+        assert_eq!(bitcoin_script!(<ScriptNum(n)>).to_bytes(), data);
+    }
+}
+
+#[test]
+fn script_num_negative_one() {
+    use bitcoin::blockdata::opcodes::all::OP_PUSHNUM_NEG1;
+    use bitcoin::blockdata::script::Instruction;
+
+    let opcode = bitcoin_script!(-1);
+    let data = bitcoin_script!(<ScriptNum(-1)>);
+    assert_eq!(opcode.to_bytes(), vec![0x4f]);
+    assert_eq!(data.to_bytes(), vec![0x01, 0x81]);
+
+    let opcode: Vec<_> = opcode.instructions().map(Result::unwrap).collect();
+    let data: Vec<_> = data.instructions().map(Result::unwrap).collect();
+    assert_eq!(opcode, vec![Instruction::Op(OP_PUSHNUM_NEG1)]);
+    assert_eq!(data, vec![Instruction::PushBytes(&[0x81])]);
+}
+
+#[test]
+fn template() {
+    use bitcoin::blockdata::script::Builder;
+    use bitcoin::PublicKey;
+    use std::str::FromStr;
+
+    let p2pk = script_template!(<_pubkey: PublicKey> OP_CHECKSIG);
+    let pubkey =
+        PublicKey::from_str("0250863ad64a87ae8a2fe83c1af1a8403cb53f53e486d8511dad8a04887e5b2352")
+            .unwrap();
+
+    let script = p2pk(pubkey);
+    assert_eq!(script, bitcoin::Script::new_p2pk(&pubkey));
+
+    let add = script_template!(<_n> OP_ADD <_n> OP_EQUAL);
+    assert_eq!(add(3i64).to_bytes(), vec![83, 147, 83, 135]);
+    assert_eq!(add(4i64).to_bytes(), vec![84, 147, 84, 135]);
+
+    // holes are only borrowed, so their type doesn't need to be `Clone`
+    struct Flag;
+    impl Pushable for Flag {
+        fn bitcoin_script_push(&self, builder: Builder) -> Builder {
+            builder.push_int(1)
+        }
+    }
+    let check = script_template!(<_flag: Flag> OP_VERIFY <_flag>);
+    assert_eq!(check(Flag).to_bytes(), vec![81, 105, 81]);
+}
+
+#[cfg(feature = "fragments")]
+#[test]
+fn fragments() {
+    use bitcoin_script::bitcoin_script_fragments;
+
+    let sub = vec![1, 2, 3];
+    let (script, fragments) = bitcoin_script_fragments! {
+        OP_DUP <sub> OP_EQUALVERIFY <2016 * 5> OP_CHECKSIG
+    };
+
+    assert_eq!(
+        script.to_bytes(),
+        vec![118, 3, 1, 2, 3, 136, 2, 0x60, 0x27, 172]
+    );
+    assert_eq!(fragments, vec![1..5, 6..9]);
+
+    let (_, fragments) = bitcoin_script_fragments!(OP_DUP OP_DROP);
+    assert!(fragments.is_empty());
+}
+
+#[test]
+fn include() {
+    let script = bitcoin_script_include!("fixtures/p2pkh.script");
+
+    assert_eq!(
+        script,
+        bitcoin_script! {
+            OP_DUP
+            OP_HASH160
+            0x1234567890abcdef1234567890abcdef12345678
+            OP_EQUALVERIFY
+            OP_CHECKSIG
+        }
+    );
+}
+
+#[test]
+fn traced() {
+    let foo = vec![1, 2, 3, 4];
+    let mut trace = Vec::new();
+    let script = bitcoin_script_traced! {
+        |item: &[u8]| trace.push(item.to_vec());
+        OP_HASH160 <foo> 1234 OP_EQUAL
+    };
+
+    assert_eq!(script.to_bytes(), vec![169, 4, 1, 2, 3, 4, 2, 210, 4, 135]);
+    assert_eq!(
+        trace,
+        vec![vec![169], vec![4, 1, 2, 3, 4], vec![2, 210, 4], vec![135]]
+    );
+}
+
+#[test]
+fn traced_checksum() {
+    let mut trace = Vec::new();
+    let script = bitcoin_script_traced! {
+        |item: &[u8]| trace.push(item.to_vec());
+        @checksum(sha256, 4);
+        1
+    };
+    assert_eq!(script.len(), 6);
+    assert_eq!(trace, vec![vec![0x51], script.to_bytes()[1..].to_vec()]);
+
+    let mut trace = Vec::new();
+    let script = bitcoin_script_traced! {
+        |item: &[u8]| trace.push(item.to_vec());
+        @checksum(sha256, 4);
+    };
+    assert_eq!(script.len(), 5);
+    assert_eq!(trace, vec![script.to_bytes()]);
+}
+
+bitcoin_script_const!(P2PKH_PREFIX: OP_DUP OP_HASH160 0x0102030405060708090a0b0c0d0e0f1011121314);
+
+#[test]
+fn script_const() {
+    const _: () = assert!(P2PKH_PREFIX_SIZE == 23);
+
+    assert_eq!(P2PKH_PREFIX_SIZE, P2PKH_PREFIX.len());
+    assert_eq!(P2PKH_PREFIX_OPCODES, 2);
+    assert_eq!(
+        bitcoin::Script::from(P2PKH_PREFIX.to_vec()),
+        bitcoin_script!(OP_DUP OP_HASH160 0x0102030405060708090a0b0c0d0e0f1011121314)
+    );
+}
+
+#[test]
+fn multisig() {
+    use bitcoin::PublicKey;
+    use std::str::FromStr;
+
+    let a =
+        PublicKey::from_str("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+            .unwrap();
+    let b =
+        PublicKey::from_str("02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5")
+            .unwrap();
+
+    assert_eq!(
+        multisig_checked!(1, a, b).unwrap(),
+        bitcoin_script!(1 <a> <b> 2 OP_CHECKMULTISIG)
+    );
+    assert_eq!(
+        multisig_checked!(2, a, b, a),
+        Err(MultisigError::DuplicateKey { index: 2, key: a })
+    );
+
+    // BIP67 sorts by the serialized keys, so 02c6... comes after 0279...
+    assert_eq!(
+        multisig_bip67!(1, b, a).unwrap(),
+        bitcoin_script!(1 <a> <b> 2 OP_CHECKMULTISIG)
+    );
+}
+
+#[test]
+fn ast() {
+    use bitcoin_script::{ScriptItem, ScriptItemKind, SourceSpan};
+
+    let ast = bitcoin_script_ast! {
+        OP_DUP
+        0x0102 <foo.bar()>
+    };
+
+    let line = line!() as usize - 4;
+    assert_eq!(
+        ast,
+        vec![
+            ScriptItem {
+                kind: ScriptItemKind::Opcode {
+                    name: "OP_DUP".to_string(),
+                    byte: 0x76,
+                },
+                span: SourceSpan { line, column: 9 },
+            },
+            ScriptItem {
+                kind: ScriptItemKind::Push {
+                    data: vec![1, 2],
+                    encoded: vec![2, 1, 2],
+                },
+                span: SourceSpan {
+                    line: line + 1,
+                    column: 9,
+                },
+            },
+            ScriptItem {
+                kind: ScriptItemKind::Escape {
+                    source: "foo.bar()".to_string(),
+                },
+                span: SourceSpan {
+                    line: line + 1,
+                    column: 16,
+                },
+            },
+        ]
+    );
+}
+
+#[test]
+fn ast_directives_and_builtins() {
+    use bitcoin_script::ScriptItemKind;
+
+    let kinds: Vec<ScriptItemKind> =
+        bitcoin_script_ast!(@be; @checksum(sha256, 4); 1000 <@redeem(x)>)
+            .into_iter()
+            .map(|item| item.kind)
+            .collect();
+    let script = bitcoin_script!(@be; 1000);
+
+    assert_eq!(
+        kinds,
+        vec![
+            ScriptItemKind::Push {
+                data: 1000i64.to_be_bytes().to_vec(),
+                encoded: script.to_bytes(),
+            },
+            ScriptItemKind::Escape {
+                source: "@ redeem(x)".to_string(),
+            },
+            ScriptItemKind::Checksum {
+                algorithm: "sha256".to_string(),
+                len: 4,
+            },
+        ]
+    );
+}